@@ -19,6 +19,8 @@ import {
 } from '@mui/material';
 import { useEffect } from 'react';
 import { useInstallerLogs } from '@/shared/hooks';
+import { ExecutionRecord } from '@/shared/types/installer';
+import { StyledOutput } from '@/shared/ui/styled-output';
 
 interface LogsModalProps {
   open: boolean;
@@ -36,6 +38,26 @@ export function LogsModal({ open, installerId, installerName, onClose }: LogsMod
     }
   }, [open, refetch]);
 
+  const renderOutput = (output: string, execution?: ExecutionRecord) => {
+    if (!execution?.stdout_spans && !execution?.stderr_spans) {
+      return output;
+    }
+    return (
+      <>
+        {execution.stdout_spans ? (
+          <StyledOutput spans={execution.stdout_spans} />
+        ) : (
+          execution.stdout
+        )}
+        {execution.stderr_spans ? (
+          <StyledOutput spans={execution.stderr_spans} />
+        ) : (
+          execution.stderr
+        )}
+      </>
+    );
+  };
+
   const getStatusIcon = (status: string) => {
     switch (status) {
       case 'success':
@@ -108,7 +130,6 @@ export function LogsModal({ open, installerId, installerName, onClose }: LogsMod
     }
   };
 
-  const reversedLogs = [...logs].reverse();
 
   return (
     <Dialog
@@ -158,7 +179,7 @@ export function LogsModal({ open, installerId, installerName, onClose }: LogsMod
               Loading logs...
             </Typography>
           </Box>
-        ) : reversedLogs.length === 0 ? (
+        ) : logs.length === 0 ? (
           <Box
             sx={{
               display: 'flex',
@@ -174,13 +195,13 @@ export function LogsModal({ open, installerId, installerName, onClose }: LogsMod
           </Box>
         ) : (
           <Timeline>
-            {reversedLogs.map((log, index) => (
+            {logs.map((log, index) => (
               <TimelineItem key={`${log.timestamp}-${index}`}>
                 <TimelineSeparator>
                   <TimelineDot color={getStatusColorForTimelineDot(log.status)}>
                     {getStatusIcon(log.status)}
                   </TimelineDot>
-                  {index < reversedLogs.length - 1 && (
+                  {index < logs.length - 1 && (
                     <TimelineConnector sx={{ bgcolor: 'rgba(38, 38, 38, 0.5)' }} />
                   )}
                 </TimelineSeparator>
@@ -270,7 +291,7 @@ export function LogsModal({ open, installerId, installerName, onClose }: LogsMod
                             display: 'block',
                           }}
                         >
-                          {log.output}
+                          {renderOutput(log.output, log.execution)}
                         </Typography>
                       </Paper>
                     )}
@@ -1,13 +1,21 @@
 import axios from 'axios';
 import {
-  BatchInstallResponse,
+  BatchEvent,
+  BatchInfo,
   BatchUninstallResponse,
   BatchUpdateResponse,
+  CatalogDiagnostics,
   INSTALL_STATUS,
+  InstallAction,
   InstallerInfo,
   InstallProgress,
   InstallResult,
   LogEntry,
+  LogExportOptions,
+  LogPage,
+  LogQuery,
+  OperationEvent,
+  OperationInfo,
 } from '../types/installer';
 import { mockInstallers } from './mock-data';
 
@@ -32,6 +40,20 @@ const delay = (ms: number): Promise<void> => {
   return new Promise<void>((resolve) => setTimeout(resolve, ms));
 };
 
+const mockOperation = (id: string, action: InstallAction): OperationInfo => {
+  const now = new Date().toISOString();
+  return {
+    id: `mock-${action}-${id}-${Date.now()}`,
+    installer_id: id,
+    action,
+    progress: { id, status: INSTALL_STATUS.PENDING, progress: 0, message: '' },
+    created_at: now,
+    started_at: null,
+    finished_at: null,
+    result: null,
+  };
+};
+
 export const installerApi = {
   getAll: async (): Promise<InstallerInfo[]> => {
     if (USE_MOCK) {
@@ -55,27 +77,19 @@ export const installerApi = {
     return response.data;
   },
 
-  install: async (id: string, version?: string): Promise<InstallResult> => {
+  install: async (id: string, version?: string): Promise<OperationInfo> => {
     if (USE_MOCK) {
-      await delay(MOCK_DELAY_INSTALL_UPDATE);
-      return {
-        success: true,
-        message: `Application installed successfully${version ? ` (version ${version})` : ''}`,
-      };
+      return mockOperation(id, 'install');
     }
-    const response = await api.post<InstallResult>(`/installers/${id}/install`, { version });
+    const response = await api.post<OperationInfo>(`/installers/${id}/install`, { version });
     return response.data;
   },
 
-  update: async (id: string, version?: string): Promise<InstallResult> => {
+  update: async (id: string, version?: string): Promise<OperationInfo> => {
     if (USE_MOCK) {
-      await delay(MOCK_DELAY_INSTALL_UPDATE);
-      return {
-        success: true,
-        message: `Application updated successfully${version ? ` (version ${version})` : ''}`,
-      };
+      return mockOperation(id, 'update');
     }
-    const response = await api.post<InstallResult>(`/installers/${id}/update`, { version });
+    const response = await api.post<OperationInfo>(`/installers/${id}/update`, { version });
     return response.data;
   },
 
@@ -97,16 +111,65 @@ export const installerApi = {
     return response.data;
   },
 
-  uninstall: async (id: string): Promise<InstallResult> => {
-    const response = await api.post<InstallResult>(`/installers/${id}/uninstall`);
+  uninstall: async (id: string): Promise<OperationInfo> => {
+    if (USE_MOCK) {
+      return mockOperation(id, 'uninstall');
+    }
+    const response = await api.post<OperationInfo>(`/installers/${id}/uninstall`);
     return response.data;
   },
 
-  batchInstall: async (ids: string[]): Promise<BatchInstallResponse> => {
-    const response = await api.post<BatchInstallResponse>('/installers/batch-install', { ids });
+  subscribeOperation: (
+    operationId: string,
+    onEvent: (event: OperationEvent) => void,
+    onError?: () => void
+  ): (() => void) => {
+    if (USE_MOCK) {
+      const timer = setTimeout(
+        () => onEvent({ type: 'exit', success: true, message: 'Operation completed successfully' }),
+        MOCK_DELAY_INSTALL_UPDATE
+      );
+      return () => clearTimeout(timer);
+    }
+    const source = new EventSource(`/api/operations/${operationId}/stream`);
+    const handle = (message: MessageEvent<string>) => {
+      const event = JSON.parse(message.data) as OperationEvent;
+      onEvent(event);
+      if (event.type === 'exit') {
+        source.close();
+      }
+    };
+    for (const name of ['output', 'progress', 'exit']) {
+      source.addEventListener(name, handle);
+    }
+    source.onerror = () => {
+      if (source.readyState === EventSource.CLOSED) {
+        onError?.();
+      }
+    };
+    return () => source.close();
+  },
+
+  batchInstall: async (ids: string[]): Promise<BatchInfo> => {
+    const response = await api.post<BatchInfo>('/installers/batch-install', { ids });
     return response.data;
   },
 
+  subscribeBatch: (batchId: string, onEvent: (event: BatchEvent) => void): (() => void) => {
+    const source = new EventSource(`/api/batches/${batchId}/stream`);
+    const handle = (message: MessageEvent<string>) => {
+      const event = JSON.parse(message.data) as BatchEvent;
+      onEvent(event);
+      if (event.type === 'finished' || (event.type === 'snapshot' && event.batch.finished_at)) {
+        source.close();
+      }
+    };
+    for (const name of ['snapshot', 'progress', 'finished']) {
+      source.addEventListener(name, handle);
+    }
+    return () => source.close();
+  },
+
   batchUninstall: async (ids: string[]): Promise<BatchUninstallResponse> => {
     if (USE_MOCK) {
       await delay(MOCK_DELAY_BATCH_START);
@@ -185,6 +248,15 @@ export const installerApi = {
     return response.data;
   },
 
+  getCatalogDiagnostics: async (): Promise<CatalogDiagnostics> => {
+    if (USE_MOCK) {
+      await delay(MOCK_DELAY_GET_BY_ID);
+      return { checked: mockInstallers.length, errors: 0, warnings: 0, files: [] };
+    }
+    const response = await api.get<CatalogDiagnostics>('/catalog/diagnostics');
+    return response.data;
+  },
+
   getLogs: async (id: string): Promise<LogEntry[]> => {
     if (USE_MOCK) {
       await delay(MOCK_DELAY_GET_BY_ID);
@@ -205,7 +277,26 @@ export const installerApi = {
         },
       ];
     }
-    const response = await api.get<LogEntry[]>(`/installers/${id}/logs`);
+    const response = await api.get<LogPage>(`/installers/${id}/logs`);
+    return response.data.entries;
+  },
+
+  queryLogs: async (query: LogQuery = {}): Promise<LogPage> => {
+    if (USE_MOCK) {
+      await delay(MOCK_DELAY_GET_BY_ID);
+      return { entries: [], next_cursor: null };
+    }
+    const response = await api.get<LogPage>('/logs', { params: query });
     return response.data;
   },
+
+  logsExportUrl: (query: LogQuery = {}, options: LogExportOptions = {}): string => {
+    const params = new URLSearchParams();
+    Object.entries({ ...query, ...options }).forEach(([key, value]) => {
+      if (value !== undefined) {
+        params.set(key, String(value));
+      }
+    });
+    return `/api/logs/export?${params.toString()}`;
+  },
 };
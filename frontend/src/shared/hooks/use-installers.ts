@@ -2,6 +2,22 @@ import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query';
 import { installerApi } from '../api/installer-api';
 import { installerQueryKeys } from '../api/query-keys';
 import { notifications } from '../lib/notifications';
+import { OperationExit, OperationInfo } from '../types/installer';
+
+function followOperation(operation: OperationInfo): Promise<OperationExit> {
+  return new Promise((resolve, reject) => {
+    const unsubscribe = installerApi.subscribeOperation(
+      operation.id,
+      (event) => {
+        if (event.type === 'exit') {
+          unsubscribe();
+          resolve(event);
+        }
+      },
+      () => reject(new Error('Lost connection to the operation stream'))
+    );
+  });
+}
 
 export function useInstallers(filters?: { category?: string; search?: string }) {
   return useQuery({
@@ -48,14 +64,14 @@ export function useInstallInstaller() {
 
   return useMutation({
     mutationFn: ({ id, version }: { id: string; version?: string }) =>
-      installerApi.install(id, version),
+      installerApi.install(id, version).then(followOperation),
     onSuccess: (result) => {
       if (result.success) {
         notifications.success(result.message);
-        queryClient.invalidateQueries({ queryKey: installerQueryKeys.all });
       } else {
         notifications.error(result.message);
       }
+      queryClient.invalidateQueries({ queryKey: installerQueryKeys.all });
     },
     onError: (error: Error) => {
       notifications.error(`Installation error: ${error.message}`);
@@ -68,14 +84,14 @@ export function useUpdateInstaller() {
 
   return useMutation({
     mutationFn: ({ id, version }: { id: string; version?: string }) =>
-      installerApi.update(id, version),
+      installerApi.update(id, version).then(followOperation),
     onSuccess: (result) => {
       if (result.success) {
         notifications.success(result.message);
-        queryClient.invalidateQueries({ queryKey: installerQueryKeys.all });
       } else {
         notifications.error(result.message);
       }
+      queryClient.invalidateQueries({ queryKey: installerQueryKeys.all });
     },
     onError: (error: Error) => {
       notifications.error(`Update error: ${error.message}`);
@@ -87,14 +103,14 @@ export function useUninstallInstaller() {
   const queryClient = useQueryClient();
 
   return useMutation({
-    mutationFn: installerApi.uninstall,
+    mutationFn: (id: string) => installerApi.uninstall(id).then(followOperation),
     onSuccess: (result) => {
       if (result.success) {
         notifications.success(result.message);
-        queryClient.invalidateQueries({ queryKey: installerQueryKeys.all });
       } else {
         notifications.error(result.message);
       }
+      queryClient.invalidateQueries({ queryKey: installerQueryKeys.all });
     },
     onError: (error: Error) => {
       notifications.error(`Uninstall error: ${error.message}`);
@@ -14,7 +14,12 @@ export interface InstallerInfo {
   current_version: string | null;
   latest_version: string | null;
   can_update: boolean;
+  ahead_of_latest?: boolean;
   dependencies: string[];
+  dependency_versions?: Record<string, string>;
+  install_path?: string | null;
+  metadata?: Record<string, unknown>;
+  messages?: string[];
   size?: string;
   rating?: number;
   downloads?: number;
@@ -25,9 +30,36 @@ export interface InstallerInfo {
   versions?: VersionInfo[];
 }
 
+export type InstallerErrorCode =
+  | 'script_not_configured'
+  | 'script_not_found'
+  | 'spawn_failed'
+  | 'non_zero_exit'
+  | 'timeout'
+  | 'cancelled'
+  | 'dependency_failed'
+  | 'invalid_config'
+  | 'io';
+
+export interface InstallerError {
+  code: InstallerErrorCode;
+  script?: string | null;
+  exit_code?: number | null;
+  stderr_tail?: string;
+  timeout_secs?: number;
+  [key: string]: unknown;
+}
+
 export interface InstallResult {
   success: boolean;
   message: string;
+  error?: InstallerError | null;
+}
+
+export interface ApiErrorBody {
+  code: string;
+  error: string;
+  [key: string]: unknown;
 }
 
 export interface BatchUninstallResponse {
@@ -49,6 +81,7 @@ export const INSTALL_STATUS = {
   INSTALLING: 'Installing',
   COMPLETED: 'Completed',
   FAILED: 'Failed',
+  CANCELLED: 'Cancelled',
 } as const;
 
 export type InstallStatus = (typeof INSTALL_STATUS)[keyof typeof INSTALL_STATUS];
@@ -60,8 +93,29 @@ export interface InstallProgress {
   message: string;
 }
 
+export type InstallAction = 'install' | 'update' | 'uninstall';
+
+export interface OperationInfo {
+  id: string;
+  installer_id: string;
+  action: InstallAction;
+  progress: InstallProgress;
+  created_at: string;
+  started_at: string | null;
+  finished_at: string | null;
+  result: InstallResult | null;
+}
+
+export type OperationEvent =
+  | { type: 'output'; stream: 'stdout' | 'stderr'; line: string; spans?: StyledSpan[] }
+  | { type: 'progress'; progress: InstallProgress }
+  | { type: 'exit'; success: boolean; message: string };
+
+export type OperationExit = Extract<OperationEvent, { type: 'exit' }>;
+
 export interface BatchInstallRequest {
   ids: string[];
+  concurrency?: number;
 }
 
 export interface BatchInstallResponse {
@@ -71,10 +125,117 @@ export interface BatchInstallResponse {
   progress: InstallProgress[];
 }
 
+export interface BatchInfo extends BatchInstallResponse {
+  id: string;
+  created_at: string;
+  finished_at: string | null;
+}
+
+export type BatchEvent =
+  | { type: 'snapshot'; batch: BatchInfo }
+  | { type: 'progress'; progress: InstallProgress }
+  | { type: 'finished'; batch: BatchInfo };
+
+export interface ExecutionRecord {
+  script: string | null;
+  manifest_revision: string | null;
+  exit_code: number | null;
+  signal: number | null;
+  started_at: string;
+  finished_at: string;
+  duration_ms: number;
+  stdout: string;
+  stderr: string;
+  stdout_raw?: string;
+  stderr_raw?: string;
+  stdout_spans?: StyledSpan[];
+  stderr_spans?: StyledSpan[];
+}
+
+export interface StyledSpan {
+  text: string;
+  fg?: string;
+  bg?: string;
+  bold?: boolean;
+  italic?: boolean;
+  underline?: boolean;
+}
+
 export interface LogEntry {
   timestamp: string;
+  run_id?: string;
   action: string;
   status: string;
   message: string;
   output: string;
+  progress?: number;
+  execution?: ExecutionRecord;
+  overflow?: string;
+}
+
+export interface LogRecord extends LogEntry {
+  installer_id: string;
+}
+
+export interface LogQuery {
+  installer?: string;
+  action?: string;
+  status?: string;
+  run_id?: string;
+  since?: string;
+  until?: string;
+  q?: string;
+  cursor?: string;
+  limit?: number;
+  order?: 'asc' | 'desc';
+}
+
+export interface LogPage {
+  entries: LogRecord[];
+  next_cursor: string | null;
+}
+
+export type LogExportFormat = 'jsonl' | 'csv' | 'text';
+
+export interface LogExportOptions {
+  format?: LogExportFormat;
+  gzip?: boolean;
+}
+
+export interface RunSummary {
+  run_id: string;
+  installer_id: string;
+  action: string;
+  status: string;
+  message: string;
+  started_at: string;
+  finished_at: string | null;
+  duration_ms: number | null;
+  entries: number;
+}
+
+export interface RunDetails extends RunSummary {
+  events: LogEntry[];
+}
+
+export interface Diagnostic {
+  severity: 'error' | 'warning';
+  message: string;
+  field?: string;
+  line?: number;
+  column?: number;
+}
+
+export interface FileDiagnostics {
+  path: string;
+  id: string | null;
+  loaded: boolean;
+  diagnostics: Diagnostic[];
+}
+
+export interface CatalogDiagnostics {
+  checked: number;
+  errors: number;
+  warnings: number;
+  files: FileDiagnostics[];
 }
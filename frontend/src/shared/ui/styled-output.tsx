@@ -0,0 +1,26 @@
+import { StyledSpan } from '@/shared/types/installer';
+
+interface StyledOutputProps {
+  spans: StyledSpan[];
+}
+
+export function StyledOutput({ spans }: StyledOutputProps) {
+  return (
+    <>
+      {spans.map((span, index) => (
+        <span
+          key={index}
+          style={{
+            color: span.fg,
+            backgroundColor: span.bg,
+            fontWeight: span.bold ? 700 : undefined,
+            fontStyle: span.italic ? 'italic' : undefined,
+            textDecoration: span.underline ? 'underline' : undefined,
+          }}
+        >
+          {span.text}
+        </span>
+      ))}
+    </>
+  );
+}
@@ -16,9 +16,35 @@ import {
   Stack,
   Typography,
 } from '@mui/material';
-import { useEffect } from 'react';
+import { useEffect, useState } from 'react';
+import { installerApi } from '@/shared/api/installer-api';
 import { useBatchInstall, useInstallers } from '@/shared/hooks';
-import { INSTALL_STATUS, type InstallStatus } from '@/shared/types/installer';
+import {
+  type BatchEvent,
+  type BatchInfo,
+  INSTALL_STATUS,
+  type InstallStatus,
+} from '@/shared/types/installer';
+
+const applyBatchEvent = (batch: BatchInfo | null, event: BatchEvent): BatchInfo | null => {
+  if (event.type !== 'progress') {
+    return event.batch;
+  }
+  if (!batch) {
+    return batch;
+  }
+  const progress = batch.progress.map((item) =>
+    item.id === event.progress.id ? event.progress : item
+  );
+  return {
+    ...batch,
+    progress,
+    completed: progress.filter((item) => item.status === INSTALL_STATUS.COMPLETED).length,
+    failed: progress.filter(
+      (item) => item.status === INSTALL_STATUS.FAILED || item.status === INSTALL_STATUS.CANCELLED
+    ).length,
+  };
+};
 
 interface BatchInstallModalProps {
   open: boolean;
@@ -30,6 +56,8 @@ interface BatchInstallModalProps {
 export function BatchInstallModal({ open, ids, onClose, onComplete }: BatchInstallModalProps) {
   const batchInstallMutation = useBatchInstall();
   const { data: allInstallers = [] } = useInstallers();
+  const [batch, setBatch] = useState<BatchInfo | null>(null);
+  const batchId = batchInstallMutation.data?.id;
 
   useEffect(() => {
     if (open && ids.length > 0 && !batchInstallMutation.isPending && !batchInstallMutation.data) {
@@ -43,6 +71,16 @@ export function BatchInstallModal({ open, ids, onClose, onComplete }: BatchInsta
     batchInstallMutation.mutate,
   ]);
 
+  useEffect(() => {
+    if (!batchId || !batchInstallMutation.data) {
+      return;
+    }
+    setBatch(batchInstallMutation.data);
+    return installerApi.subscribeBatch(batchId, (event) => {
+      setBatch((current) => applyBatchEvent(current, event));
+    });
+  }, [batchId, batchInstallMutation.data]);
+
   const getInstallerName = (id: string) => {
     return allInstallers.find((inst) => inst.id === id)?.name || id;
   };
@@ -62,27 +100,21 @@ export function BatchInstallModal({ open, ids, onClose, onComplete }: BatchInsta
 
   const handleClose = () => {
     if (!batchInstallMutation.isPending) {
-      if (batchInstallMutation.data) {
+      if (batch) {
         onComplete();
       }
       onClose();
     }
   };
 
-  const allCompleted = batchInstallMutation.data
-    ? batchInstallMutation.data.completed + batchInstallMutation.data.failed ===
-      batchInstallMutation.data.total
-    : false;
+  const allCompleted = batch ? batch.completed + batch.failed === batch.total : false;
 
-  const progress = batchInstallMutation.data?.progress || [];
+  const progress = batch?.progress || [];
   const activeProgress = progress.filter((item) => item.status !== INSTALL_STATUS.PENDING);
   const activeTotal = activeProgress.length;
   const overallProgress =
-    batchInstallMutation.data && activeTotal > 0
-      ? Math.round(
-          ((batchInstallMutation.data.completed + batchInstallMutation.data.failed) / activeTotal) *
-            100
-        )
+    batch && activeTotal > 0
+      ? Math.round(((batch.completed + batch.failed) / activeTotal) * 100)
       : 0;
 
   return (
@@ -143,7 +175,7 @@ export function BatchInstallModal({ open, ids, onClose, onComplete }: BatchInsta
       <DialogContent sx={{ p: 2 }}>
         <Stack spacing={1.5}>
           {/* Overall progress */}
-          {batchInstallMutation.data && (
+          {batch && (
             <Box
               sx={{
                 bgcolor: 'rgba(10, 10, 10, 0.6)',
@@ -183,7 +215,7 @@ export function BatchInstallModal({ open, ids, onClose, onComplete }: BatchInsta
                   mb: 1,
                   '& .MuiLinearProgress-bar': {
                     bgcolor:
-                      allCompleted && batchInstallMutation.data.failed === 0
+                      allCompleted && batch.failed === 0
                         ? '#10b981'
                         : '#8b5cf6',
                     borderRadius: 0.5,
@@ -192,22 +224,21 @@ export function BatchInstallModal({ open, ids, onClose, onComplete }: BatchInsta
               />
               <Box sx={{ display: 'flex', alignItems: 'center', gap: 1.5, fontSize: '0.6875rem' }}>
                 <Typography variant="caption" sx={{ color: '#10b981', fontSize: '0.6875rem' }}>
-                  ✓ {batchInstallMutation.data.completed}
+                  ✓ {batch.completed}
                 </Typography>
                 <Typography variant="caption" sx={{ fontSize: '0.6875rem', color: '#737373' }}>
-                  {batchInstallMutation.data.completed + batchInstallMutation.data.failed}/
-                  {batchInstallMutation.data.total}
+                  {batch.completed + batch.failed}/{batch.total}
                 </Typography>
-                {batchInstallMutation.data.failed > 0 && (
+                {batch.failed > 0 && (
                   <Typography variant="caption" sx={{ color: '#ef4444', fontSize: '0.6875rem' }}>
-                    ✗ {batchInstallMutation.data.failed}
+                    ✗ {batch.failed}
                   </Typography>
                 )}
               </Box>
             </Box>
           )}
 
-          {!batchInstallMutation.data && batchInstallMutation.isPending && (
+          {!batch && batchInstallMutation.isPending && (
             <Box sx={{ display: 'flex', alignItems: 'center', gap: 1.5, py: 2 }}>
               <CircularProgress size={20} sx={{ color: '#8b5cf6' }} />
               <Typography variant="body2" sx={{ color: '#a3a3a3', fontSize: '0.8125rem' }}>
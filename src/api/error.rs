@@ -0,0 +1,93 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use crate::installer::dependency::DependencyError;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+// Тело ошибки всегда содержит стабильный `code` и человекочитаемый `error`;
+// остальные поля зависят от вида ошибки.
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("Установщик {0} не найден")]
+    InstallerNotFound(String),
+
+    #[error("Операция {0} не найдена")]
+    OperationNotFound(String),
+
+    #[error("Запуск {0} не найден")]
+    RunNotFound(String),
+
+    #[error("Пакет {0} не найден")]
+    BatchNotFound(String),
+
+    #[error("Для установщика {0} ещё не запускались операции")]
+    NoOperations(String),
+
+    #[error("Операция {0} уже завершена")]
+    OperationFinished(String),
+
+    #[error("{0}")]
+    InvalidQuery(String),
+
+    #[error("{0}")]
+    Dependency(DependencyError),
+
+    #[error("От {id} зависят установленные приложения: {}", .dependents.join(", "))]
+    DependentsInstalled { id: String, dependents: Vec<String> },
+}
+
+impl ApiError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::InstallerNotFound(_) => "installer_not_found",
+            ApiError::OperationNotFound(_) => "operation_not_found",
+            ApiError::NoOperations(_) => "no_operations",
+            ApiError::RunNotFound(_) => "run_not_found",
+            ApiError::BatchNotFound(_) => "batch_not_found",
+            ApiError::OperationFinished(_) => "operation_finished",
+            ApiError::InvalidQuery(_) => "invalid_query",
+            ApiError::Dependency(DependencyError::Missing { .. }) => "dependency_missing",
+            ApiError::Dependency(DependencyError::Cycle { .. }) => "dependency_cycle",
+            ApiError::DependentsInstalled { .. } => "dependents_installed",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::InstallerNotFound(_)
+            | ApiError::OperationNotFound(_)
+            | ApiError::NoOperations(_)
+            | ApiError::RunNotFound(_)
+            | ApiError::BatchNotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::OperationFinished(_) | ApiError::DependentsInstalled { .. } => StatusCode::CONFLICT,
+            ApiError::InvalidQuery(_) => StatusCode::BAD_REQUEST,
+            ApiError::Dependency(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    fn details(&self) -> Value {
+        match self {
+            ApiError::InstallerNotFound(id) | ApiError::NoOperations(id) => json!({ "id": id }),
+            ApiError::OperationNotFound(id) | ApiError::OperationFinished(id) => json!({ "operation_id": id }),
+            ApiError::RunNotFound(id) => json!({ "run_id": id }),
+            ApiError::BatchNotFound(id) => json!({ "batch_id": id }),
+            ApiError::InvalidQuery(_) => json!({}),
+            ApiError::Dependency(e) => serde_json::to_value(e).unwrap_or_default(),
+            ApiError::DependentsInstalled { id, dependents } => json!({ "id": id, "dependents": dependents }),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let mut body = match self.details() {
+            Value::Object(details) => Value::Object(details),
+            _ => json!({}),
+        };
+        body["code"] = Value::String(self.code().to_string());
+        body["error"] = Value::String(self.to_string());
+        (self.status(), Json(body)).into_response()
+    }
+}
@@ -1,34 +1,61 @@
+mod error;
+
 use axum::{
-    extract::Path,
-    http::StatusCode,
-    response::Json,
+    body::{Body, Bytes},
+    extract::{Path, Query},
+    http::{header, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
     routing::{get, post},
     Router,
 };
 use crate::installer::{
-    InstallerInfo, InstallResult, loader::InstallerLoader,
-    BatchInstallRequest, BatchInstallResponse, InstallProgress, InstallStatus,
-    Logger, LogEntry
+    InstallerInfo, InstallResult, loader::{CatalogDiagnostics, InstallerLoader},
+    BatchInstallRequest, InstallProgress, InstallStatus,
+    InstallAction, InstallerError, Logger, LogEntry, LogPage, LogQuery, LogRetention, Operation, OperationManager,
+    RunDetails, RunSummary,
+    batch::{Batch, BatchInfo, BatchManager},
+    export::{ExportOptions, LogExporter},
+    schema,
+    operation::{OperationEvent, OperationInfo},
+    dependency::{dependents_uninstall_order, resolve_install_order, DependencyError},
+    version::satisfies
 };
 use crate::installers::create_all_installers;
+use error::ApiError;
+use futures::future;
+use futures::stream::{self, Stream, StreamExt};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::convert::Infallible;
 use std::sync::Arc;
 use crate::installer::Installer;
 use std::path::PathBuf;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{watch, Semaphore};
+
+const DEFAULT_BATCH_CONCURRENCY: usize = 4;
+const EXPORT_CHUNK_ENTRIES: usize = 200;
 
 pub struct AppState {
     pub static_installers: Arc<Vec<Arc<dyn Installer>>>,
     pub loader: Arc<InstallerLoader>,
     pub logger: Arc<Logger>,
+    pub operations: Arc<OperationManager>,
+    pub batches: Arc<BatchManager>,
 }
 
 pub fn create_router() -> Router {
     let static_installers = Arc::new(create_all_installers());
     
     let scripts_dir = PathBuf::from("installers");
-    let loader = Arc::new(InstallerLoader::new(scripts_dir));
+    let builtin_ids = static_installers.iter().map(|i| i.id().to_string());
+    let loader = Arc::new(InstallerLoader::new(scripts_dir).with_builtin_ids(builtin_ids));
     
     let logs_dir = PathBuf::from("logs");
-    let logger = Arc::new(Logger::new(logs_dir));
+    let logger = Arc::new(Logger::new(logs_dir, LogRetention::from_env()));
     
     let loader_clone = Arc::clone(&loader);
     tokio::spawn(async move {
@@ -42,6 +69,8 @@ pub fn create_router() -> Router {
         static_installers,
         loader,
         logger,
+        operations: Arc::new(OperationManager::new()),
+        batches: Arc::new(BatchManager::new()),
     };
 
     Router::new()
@@ -51,8 +80,23 @@ pub fn create_router() -> Router {
         .route("/api/installers/:id/update", post(update_installer))
         .route("/api/installers/:id/uninstall", post(uninstall_installer))
         .route("/api/installers/:id/logs", get(get_installer_logs))
+        .route("/api/installers/:id/logs/export", get(export_installer_logs))
+        .route("/api/logs", get(get_logs))
+        .route("/api/logs/export", get(export_logs))
+        .route("/api/installers/:id/operation", get(get_installer_operation))
+        .route("/api/installers/:id/runs", get(get_installer_runs))
+        .route("/api/runs/:run_id", get(get_run))
+        .route("/api/runs/:run_id/export", get(export_run))
         .route("/api/installers/batch-install", post(batch_install))
+        .route("/api/batches/:batch_id", get(get_batch))
+        .route("/api/batches/:batch_id/stream", get(stream_batch))
         .route("/api/categories", get(get_categories))
+        .route("/api/catalog/diagnostics", get(get_catalog_diagnostics))
+        .route("/api/schema/installer-manifest", get(get_manifest_schema))
+        .route("/api/operations", get(get_operations))
+        .route("/api/operations/:op_id", get(get_operation))
+        .route("/api/operations/:op_id/stream", get(stream_operation))
+        .route("/api/operations/:op_id/cancel", post(cancel_operation))
         .with_state(Arc::new(state))
 }
 
@@ -75,7 +119,7 @@ async fn get_all_installers(
 async fn get_installer(
     axum::extract::State(state): axum::extract::State<Arc<AppState>>,
     Path(id): Path<String>,
-) -> Result<Json<InstallerInfo>, StatusCode> {
+) -> Result<Json<InstallerInfo>, ApiError> {
     if let Some(installer) = state.static_installers.iter().find(|i| i.id() == id) {
         return Ok(Json(installer.get_info().await));
     }
@@ -84,228 +128,717 @@ async fn get_installer(
         return Ok(Json(installer.get_info().await));
     }
     
-    Err(StatusCode::NOT_FOUND)
+    Err(ApiError::InstallerNotFound(id))
 }
 
-async fn install_installer(
-    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
-    Path(id): Path<String>,
-) -> Result<Json<InstallResult>, StatusCode> {
-    let logger = Arc::clone(&state.logger);
-    
-    if let Some(installer) = state.static_installers.iter().find(|i| i.id() == id) {
-        logger.log(&id, "install", "started", "Начало установки", "");
-        match installer.install().await {
-            Ok(result) => {
-                let status = if result.success { "success" } else { "failed" };
-                logger.log(&id, "install", status, &result.message, &result.message);
-                return Ok(Json(result));
-            }
-            Err(e) => {
-                logger.log(&id, "install", "failed", &e, &e);
-                return Ok(Json(InstallResult {
-                    success: false,
-                    message: e,
-                }));
+fn all_installers(state: &AppState) -> Vec<Arc<dyn Installer>> {
+    state
+        .static_installers
+        .iter()
+        .map(Arc::clone)
+        .chain(state.loader.get_all())
+        .collect()
+}
+
+fn find_installer(state: &AppState, id: &str) -> Option<Arc<dyn Installer>> {
+    state
+        .static_installers
+        .iter()
+        .find(|i| i.id() == id)
+        .map(Arc::clone)
+        .or_else(|| state.loader.get(id))
+}
+
+async fn execute_operation(
+    state: &AppState,
+    installer: Arc<dyn Installer>,
+    operation: Arc<Operation>,
+) -> InstallResult {
+    let id = installer.id().to_string();
+    let action = operation.action();
+    let log = |entry: LogEntry| state.logger.append(&id, entry.with_run(operation.id()));
+
+    let _guard = tokio::select! {
+        biased;
+        _ = operation.cancelled() => {
+            let error = InstallerError::Cancelled;
+            let result = InstallResult {
+                success: false,
+                message: error.to_string(),
+                error: Some(error),
+            };
+            log(LogEntry::new(action.as_str(), "cancelled", &result.message, ""));
+            operation.finish(&result);
+            return result;
+        }
+        guard = state.operations.lock_installer(&id) => guard,
+    };
+
+    operation.start();
+    log(LogEntry::new(action.as_str(), "started", action.started_message(), ""));
+
+    let log_progress = |progress: InstallProgress| {
+        log(LogEntry::new(action.as_str(), "progress", &progress.message, "").with_progress(progress.progress))
+    };
+    let mut events = operation.subscribe_live();
+    let forward_progress = async {
+        loop {
+            match events.recv().await {
+                Ok(OperationEvent::Progress { progress }) => log_progress(progress),
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
             }
         }
+        std::future::pending::<()>().await
+    };
+    let outcome = tokio::select! {
+        outcome = installer.run_action(action, &operation) => outcome,
+        _ = forward_progress => unreachable!(),
+    };
+    while let Ok(event) = events.try_recv() {
+        if let OperationEvent::Progress { progress } = event {
+            log_progress(progress);
+        }
     }
-    
-    if let Some(installer) = state.loader.get(&id) {
-        logger.log(&id, "install", "started", "Начало установки", "");
-        match installer.install().await {
-            Ok(result) => {
-                let status = if result.success { "success" } else { "failed" };
-                logger.log(&id, "install", status, &result.message, &result.message);
-                return Ok(Json(result));
+
+    let result = match outcome {
+        Ok(result) => {
+            let status = if result.success { "success" } else { "failed" };
+            log(LogEntry::new(action.as_str(), status, &result.message, &result.message)
+                .with_execution(operation.take_execution()));
+            result
+        }
+        Err(e) => {
+            let message = format!("{}: {}", action.failed_message(), e);
+            let status = match e {
+                InstallerError::Timeout { .. } => "timeout",
+                InstallerError::Cancelled => "cancelled",
+                _ => "failed",
+            };
+            // Очистка нужна, только если скрипт действительно запускался.
+            let execution = operation.take_execution();
+            if execution.is_some() && matches!(e, InstallerError::Timeout { .. } | InstallerError::Cancelled) {
+                run_cleanup(installer.as_ref(), &log).await;
             }
-            Err(e) => {
-                logger.log(&id, "install", "failed", &e, &e);
-                return Ok(Json(InstallResult {
-                    success: false,
-                    message: e,
-                }));
+            log(LogEntry::new(action.as_str(), status, &message, &message).with_execution(execution));
+            InstallResult {
+                success: false,
+                message,
+                error: Some(e),
             }
         }
+    };
+    operation.finish(&result);
+
+    result
+}
+
+async fn run_cleanup(installer: &dyn Installer, log: impl Fn(LogEntry)) {
+    match installer.cleanup().await {
+        Some(Ok(result)) => log(LogEntry::new("cleanup", "success", &result.message, &result.message)),
+        Some(Err(e)) => {
+            let message = format!("Ошибка очистки: {}", e);
+            log(LogEntry::new("cleanup", "failed", &message, &message))
+        }
+        None => {}
     }
-    
-    Err(StatusCode::NOT_FOUND)
 }
 
-async fn update_installer(
-    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
-    Path(id): Path<String>,
-) -> Result<Json<InstallResult>, StatusCode> {
-    let logger = Arc::clone(&state.logger);
-    
-    if let Some(installer) = state.static_installers.iter().find(|i| i.id() == id) {
-        logger.log(&id, "update", "started", "Начало обновления", "");
-        match installer.update().await {
-            Ok(result) => {
-                let status = if result.success { "success" } else { "failed" };
-                logger.log(&id, "update", status, &result.message, &result.message);
-                return Ok(Json(result));
-            }
-            Err(e) => {
-                logger.log(&id, "update", "failed", &e, &e);
-                return Ok(Json(InstallResult {
-                    success: false,
-                    message: e,
-                }));
+async fn run_prerequisite(
+    state: &AppState,
+    prerequisite: &Arc<dyn Installer>,
+    action: InstallAction,
+) -> Result<(), String> {
+    let operation = state.operations.create(prerequisite.id(), action);
+    let result = execute_operation(state, Arc::clone(prerequisite), operation).await;
+    if result.success {
+        Ok(())
+    } else {
+        Err(result.message)
+    }
+}
+
+fn version_requirements(dependency_id: &str, dependents: &[Arc<dyn Installer>]) -> Vec<(String, String)> {
+    dependents
+        .iter()
+        .filter_map(|d| {
+            let requirement = d.dependency_versions().remove(dependency_id)?;
+            Some((requirement, d.id().to_string()))
+        })
+        .collect()
+}
+
+fn unmet_requirement(
+    dependency: &dyn Installer,
+    version: Option<&str>,
+    requirements: &[(String, String)],
+) -> Option<String> {
+    let Some(version) = version else {
+        return Some(format!("не удалось определить версию {}", dependency.id()));
+    };
+
+    for (requirement, required_by) in requirements {
+        match satisfies(version, requirement, dependency.version_scheme()) {
+            Ok(true) => {}
+            Ok(false) => {
+                return Some(format!(
+                    "{} {} не удовлетворяет требованию \"{}\" (для {})",
+                    dependency.id(),
+                    version,
+                    requirement,
+                    required_by
+                ))
             }
+            Err(e) => return Some(format!("{} (для {})", e, required_by)),
         }
     }
-    
-    if let Some(installer) = state.loader.get(&id) {
-        logger.log(&id, "update", "started", "Начало обновления", "");
-        match installer.update().await {
-            Ok(result) => {
-                let status = if result.success { "success" } else { "failed" };
-                logger.log(&id, "update", status, &result.message, &result.message);
-                return Ok(Json(result));
+    None
+}
+
+fn dependency_failed(dependency: &dyn Installer, message: String) -> InstallerError {
+    InstallerError::DependencyFailed {
+        dependency: dependency.id().to_string(),
+        message,
+    }
+}
+
+async fn ensure_dependency(
+    state: &AppState,
+    dependency: &Arc<dyn Installer>,
+    requirements: &[(String, String)],
+) -> Result<(), InstallerError> {
+    let fail = |message: String| dependency_failed(dependency.as_ref(), message);
+
+    // Общую зависимость нескольких одновременных установок проверяет и ставит
+    // только одна из них; остальные дожидаются и видят её уже установленной.
+    let _guard = state.operations.lock_dependency(dependency.id()).await;
+    let installed = dependency.check_installed().await;
+    if !installed {
+        run_prerequisite(state, dependency, InstallAction::Install)
+            .await
+            .map_err(|e| fail(format!("Не удалось установить зависимость {}: {}", dependency.id(), e)))?;
+    }
+    if requirements.is_empty() {
+        return Ok(());
+    }
+
+    let current = dependency.get_current_version().await;
+    let Some(problem) = unmet_requirement(dependency.as_ref(), current.as_deref(), requirements) else {
+        return Ok(());
+    };
+    if !installed {
+        return Err(fail(format!("Установленная зависимость не подходит: {}", problem)));
+    }
+
+    let latest = dependency.get_latest_version().await;
+    if let Some(latest_problem) = unmet_requirement(dependency.as_ref(), latest.as_deref(), requirements) {
+        return Err(fail(format!(
+            "Зависимость не подходит: {}; обновление не поможет: {}",
+            problem, latest_problem
+        )));
+    }
+
+    run_prerequisite(state, dependency, InstallAction::Update)
+        .await
+        .map_err(|e| fail(format!("Не удалось обновить зависимость {}: {}", dependency.id(), e)))?;
+
+    let current = dependency.get_current_version().await;
+    match unmet_requirement(dependency.as_ref(), current.as_deref(), requirements) {
+        Some(problem) => Err(fail(format!("После обновления зависимость всё ещё не подходит: {}", problem))),
+        None => Ok(()),
+    }
+}
+
+async fn uninstall_dependent(
+    state: &AppState,
+    dependent: &Arc<dyn Installer>,
+    target_id: &str,
+) -> Result<(), InstallerError> {
+    if !dependent.check_installed().await {
+        return Ok(());
+    }
+    let operation = state.operations.create(dependent.id(), InstallAction::Uninstall);
+    let entry = LogEntry::new(
+        InstallAction::Uninstall.as_str(),
+        "cascade",
+        &format!("Каскадное удаление: зависит от {}", target_id),
+        "",
+    );
+    state.logger.append(dependent.id(), entry.with_run(operation.id()));
+
+    let result = execute_operation(state, Arc::clone(dependent), operation).await;
+    if result.success {
+        return Ok(());
+    }
+    let message = format!("Не удалось удалить зависимое приложение {}: {}", dependent.id(), result.message);
+    Err(dependency_failed(dependent.as_ref(), message))
+}
+
+async fn execute_with_prerequisites(
+    state: &AppState,
+    installer: Arc<dyn Installer>,
+    operation: Arc<Operation>,
+    prerequisites: Vec<Arc<dyn Installer>>,
+) -> InstallResult {
+    let action = operation.action();
+    let mut dependents = prerequisites.clone();
+    dependents.push(Arc::clone(&installer));
+
+    for prerequisite in &prerequisites {
+        if operation.is_cancelled() {
+            break;
+        }
+
+        let outcome = match action {
+            InstallAction::Uninstall => {
+                operation.set_message(&format!("Удаление зависимого приложения {}", prerequisite.id()));
+                uninstall_dependent(state, prerequisite, installer.id()).await
             }
-            Err(e) => {
-                logger.log(&id, "update", "failed", &e, &e);
-                return Ok(Json(InstallResult {
-                    success: false,
-                    message: e,
-                }));
+            _ => {
+                operation.set_message(&format!("Проверка зависимости {}", prerequisite.id()));
+                let requirements = version_requirements(prerequisite.id(), &dependents);
+                ensure_dependency(state, prerequisite, &requirements).await
             }
+        };
+
+        if let Err(error) = outcome {
+            let result = InstallResult {
+                success: false,
+                message: error.to_string(),
+                error: Some(error),
+            };
+            let entry = LogEntry::new(action.as_str(), "failed", &result.message, "");
+            state.logger.append(installer.id(), entry.with_run(operation.id()));
+            operation.finish(&result);
+            return result;
         }
     }
-    
-    Err(StatusCode::NOT_FOUND)
+
+    execute_operation(state, installer, operation).await
+}
+
+fn spawn_operation(
+    state: Arc<AppState>,
+    installer: Arc<dyn Installer>,
+    action: InstallAction,
+    prerequisites: Vec<Arc<dyn Installer>>,
+) -> OperationInfo {
+    let operation = state.operations.create(installer.id(), action);
+    let info = operation.info();
+
+    tokio::spawn(async move {
+        execute_with_prerequisites(&state, installer, operation, prerequisites).await;
+    });
+
+    info
+}
+
+fn install_plan(state: &AppState, ids: &[String]) -> Result<Vec<Arc<dyn Installer>>, DependencyError> {
+    resolve_install_order(ids, |id| find_installer(state, id))
+}
+
+async fn install_installer(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<(StatusCode, Json<OperationInfo>), ApiError> {
+    let installer = find_installer(&state, &id).ok_or_else(|| ApiError::InstallerNotFound(id.clone()))?;
+    let mut plan = install_plan(&state, std::slice::from_ref(&id)).map_err(ApiError::Dependency)?;
+    plan.retain(|i| i.id() != id);
+
+    let info = spawn_operation(state, installer, InstallAction::Install, plan);
+    Ok((StatusCode::ACCEPTED, Json(info)))
+}
+
+async fn update_installer(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<(StatusCode, Json<OperationInfo>), ApiError> {
+    let installer = find_installer(&state, &id).ok_or_else(|| ApiError::InstallerNotFound(id.clone()))?;
+    let info = spawn_operation(state, installer, InstallAction::Update, Vec::new());
+    Ok((StatusCode::ACCEPTED, Json(info)))
+}
+
+#[derive(Debug, Deserialize)]
+struct UninstallQuery {
+    #[serde(default)]
+    cascade: bool,
 }
 
 async fn uninstall_installer(
     axum::extract::State(state): axum::extract::State<Arc<AppState>>,
     Path(id): Path<String>,
-) -> Result<Json<InstallResult>, StatusCode> {
-    let logger = Arc::clone(&state.logger);
-    
-    if let Some(installer) = state.static_installers.iter().find(|i| i.id() == id) {
-        logger.log(&id, "uninstall", "started", "Начало удаления", "");
-        match installer.uninstall().await {
-            Ok(result) => {
-                let status = if result.success { "success" } else { "failed" };
-                logger.log(&id, "uninstall", status, &result.message, &result.message);
-                return Ok(Json(result));
-            }
-            Err(e) => {
-                logger.log(&id, "uninstall", "failed", &e, &e);
-                return Ok(Json(InstallResult {
-                    success: false,
-                    message: e,
-                }));
-            }
+    Query(query): Query<UninstallQuery>,
+) -> Result<(StatusCode, Json<OperationInfo>), ApiError> {
+    let installer = find_installer(&state, &id).ok_or_else(|| ApiError::InstallerNotFound(id.clone()))?;
+
+    let mut dependents = Vec::new();
+    for dependent in dependents_uninstall_order(&id, &all_installers(&state)) {
+        if dependent.check_installed().await {
+            dependents.push(dependent);
         }
     }
-    
-    if let Some(installer) = state.loader.get(&id) {
-        logger.log(&id, "uninstall", "started", "Начало удаления", "");
-        match installer.uninstall().await {
-            Ok(result) => {
-                let status = if result.success { "success" } else { "failed" };
-                logger.log(&id, "uninstall", status, &result.message, &result.message);
-                return Ok(Json(result));
-            }
-            Err(e) => {
-                logger.log(&id, "uninstall", "failed", &e, &e);
-                return Ok(Json(InstallResult {
-                    success: false,
-                    message: e,
-                }));
-            }
-        }
+
+    if !dependents.is_empty() && !query.cascade {
+        return Err(ApiError::DependentsInstalled {
+            id,
+            dependents: dependents.iter().map(|d| d.id().to_string()).collect(),
+        });
     }
-    
-    Err(StatusCode::NOT_FOUND)
+
+    let info = spawn_operation(state, installer, InstallAction::Uninstall, dependents);
+    Ok((StatusCode::ACCEPTED, Json(info)))
+}
+
+async fn get_operations(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+) -> Result<Json<Vec<OperationInfo>>, StatusCode> {
+    Ok(Json(state.operations.list()))
+}
+
+async fn get_operation(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    Path(op_id): Path<String>,
+) -> Result<Json<OperationInfo>, ApiError> {
+    state
+        .operations
+        .get(&op_id)
+        .map(|operation| Json(operation.info()))
+        .ok_or(ApiError::OperationNotFound(op_id))
+}
+
+async fn cancel_operation(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    Path(op_id): Path<String>,
+) -> Result<(StatusCode, Json<OperationInfo>), ApiError> {
+    let operation = state
+        .operations
+        .get(&op_id)
+        .ok_or_else(|| ApiError::OperationNotFound(op_id.clone()))?;
+    if !operation.cancel() {
+        return Err(ApiError::OperationFinished(op_id));
+    }
+    Ok((StatusCode::ACCEPTED, Json(operation.info())))
+}
+
+async fn get_installer_operation(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<OperationInfo>, ApiError> {
+    find_installer(&state, &id).ok_or_else(|| ApiError::InstallerNotFound(id.clone()))?;
+    state
+        .operations
+        .latest_for(&id)
+        .map(|operation| Json(operation.info()))
+        .ok_or(ApiError::NoOperations(id))
+}
+
+async fn stream_operation(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    Path(op_id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let operation = state
+        .operations
+        .get(&op_id)
+        .ok_or(ApiError::OperationNotFound(op_id))?;
+    let events = operation.stream().map(|event| {
+        Ok(Event::default()
+            .event(event.name())
+            .json_data(&event)
+            .unwrap_or_default())
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
 }
 
 async fn get_installer_logs(
     axum::extract::State(state): axum::extract::State<Arc<AppState>>,
     Path(id): Path<String>,
-) -> Result<Json<Vec<LogEntry>>, StatusCode> {
-    let logs = state.logger.get_logs(&id);
-    Ok(Json(logs))
+    Query(mut query): Query<LogQuery>,
+) -> Result<Json<LogPage>, ApiError> {
+    query.installer = Some(id);
+    get_logs(axum::extract::State(state), Query(query)).await
 }
 
-async fn batch_install(
+async fn get_logs(
     axum::extract::State(state): axum::extract::State<Arc<AppState>>,
-    axum::Json(request): axum::Json<BatchInstallRequest>,
-) -> Result<Json<BatchInstallResponse>, StatusCode> {
-    let logger = Arc::clone(&state.logger);
-    let mut progress = Vec::new();
-    let mut completed = 0;
-    let mut failed = 0;
-
-    for id in &request.ids {
-        progress.push(InstallProgress {
-            id: id.clone(),
-            status: InstallStatus::Pending,
-            progress: 0,
-            message: "Ожидание...".to_string(),
+    Query(query): Query<LogQuery>,
+) -> Result<Json<LogPage>, ApiError> {
+    state
+        .logger
+        .query(&query)
+        .map(Json)
+        .map_err(ApiError::InvalidQuery)
+}
+
+async fn get_installer_runs(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<RunSummary>>, StatusCode> {
+    Ok(Json(state.logger.get_runs(&id)))
+}
+
+async fn get_run(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    Path(run_id): Path<String>,
+) -> Result<Json<RunDetails>, ApiError> {
+    state
+        .logger
+        .get_run(&run_id)
+        .map(Json)
+        .ok_or(ApiError::RunNotFound(run_id))
+}
+
+async fn export_installer_logs(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(mut query): Query<LogQuery>,
+    Query(options): Query<ExportOptions>,
+) -> Result<Response, ApiError> {
+    let scope = format!("logs-{}", id);
+    query.installer = Some(id);
+    export_response(&state, &query, &options, &scope)
+}
+
+async fn export_logs(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    Query(query): Query<LogQuery>,
+    Query(options): Query<ExportOptions>,
+) -> Result<Response, ApiError> {
+    let scope = match &query.installer {
+        Some(id) => format!("logs-{}", id),
+        None => "logs".to_string(),
+    };
+    export_response(&state, &query, &options, &scope)
+}
+
+async fn export_run(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    Path(run_id): Path<String>,
+    Query(options): Query<ExportOptions>,
+) -> Result<Response, ApiError> {
+    if state.logger.get_run(&run_id).is_none() {
+        return Err(ApiError::RunNotFound(run_id));
+    }
+    let query = LogQuery {
+        run_id: Some(run_id.clone()),
+        ..Default::default()
+    };
+    export_response(&state, &query, &options, &format!("run-{}", run_id))
+}
+
+// Записи выбираются сразу, а форматирование и сжатие идут порциями по мере
+// отправки тела ответа.
+fn export_response(
+    state: &AppState,
+    query: &LogQuery,
+    options: &ExportOptions,
+    scope: &str,
+) -> Result<Response, ApiError> {
+    let mut records = state
+        .logger
+        .export(query)
+        .map_err(ApiError::InvalidQuery)?
+        .into_iter();
+    let exporter = LogExporter::new(options);
+
+    let chunks = stream::unfold(Some(exporter), move |exporter| {
+        let next = exporter.map(|mut exporter| {
+            let batch: Vec<_> = records.by_ref().take(EXPORT_CHUNK_ENTRIES).collect();
+            if batch.is_empty() {
+                (exporter.finish(), None)
+            } else {
+                (exporter.write(&batch), Some(exporter))
+            }
         });
+        future::ready(next.map(|(bytes, exporter)| (Ok::<_, Infallible>(Bytes::from(bytes)), exporter)))
+    });
+
+    let disposition = format!("attachment; filename=\"{}\"", options.file_name(scope));
+    Ok((
+        [
+            (header::CONTENT_TYPE, options.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        Body::from_stream(chunks),
+    )
+        .into_response())
+}
+
+fn failed_progress(id: &str, message: &str) -> InstallProgress {
+    InstallProgress {
+        id: id.to_string(),
+        status: InstallStatus::Failed,
+        progress: 100,
+        message: message.to_string(),
     }
+}
 
-    let total = request.ids.len();
-
-    for (index, id) in request.ids.iter().enumerate() {
-        let progress_item = progress.get_mut(index).unwrap();
-        progress_item.status = InstallStatus::Installing;
-        progress_item.progress = 10;
-        progress_item.message = "Начало установки...".to_string();
-        logger.log(id, "install", "started", "Начало установки", "");
-
-        progress_item.progress = 30;
-        progress_item.message = "Установка...".to_string();
-
-        let install_result = if let Some(installer) = state.static_installers.iter().find(|i| i.id() == id) {
-            installer.install().await
-        } else if let Some(installer) = state.loader.get(id) {
-            installer.install().await
-        } else {
-            progress_item.status = InstallStatus::Failed;
-            progress_item.progress = 100;
-            progress_item.message = "Установщик не найден".to_string();
-            logger.log(id, "install", "failed", "Установщик не найден", "");
-            failed += 1;
-            continue;
+// Зависимость, которую ставит другой элемент того же пакета.
+struct BatchWait {
+    dependency: Arc<dyn Installer>,
+    requirements: Vec<(String, String)>,
+    done: watch::Receiver<Option<bool>>,
+}
+
+async fn batch_install_item(
+    state: &AppState,
+    batch: &Batch,
+    installer: Arc<dyn Installer>,
+    prerequisites: Vec<Arc<dyn Installer>>,
+    waits: Vec<BatchWait>,
+    semaphore: &Semaphore,
+    done: watch::Sender<Option<bool>>,
+) {
+    let id = installer.id().to_string();
+
+    for BatchWait { dependency, requirements, done: mut dependency_done } in waits {
+        let succeeded = match dependency_done.wait_for(Option::is_some).await {
+            Ok(result) => result.unwrap_or(false),
+            Err(_) => false,
+        };
+        let problem = match succeeded {
+            false => Some(format!("Зависимость {} не была установлена", dependency.id())),
+            true if requirements.is_empty() => None,
+            true => {
+                let current = dependency.get_current_version().await;
+                unmet_requirement(dependency.as_ref(), current.as_deref(), &requirements)
+                    .map(|problem| format!("Установленная зависимость не подходит: {}", problem))
+            }
         };
+        if let Some(message) = problem {
+            state.logger.log(&id, "install", "failed", &message, "");
+            batch.update(failed_progress(&id, &message));
+            done.send_replace(Some(false));
+            return;
+        }
+    }
+
+    let _permit = semaphore.acquire().await;
+    let operation = state.operations.create(&id, InstallAction::Install);
 
-        match install_result {
-            Ok(result) => {
-                if result.success {
-                    progress_item.status = InstallStatus::Completed;
-                    progress_item.progress = 100;
-                    progress_item.message = result.message.clone();
-                    logger.log(id, "install", "success", &result.message, &result.message);
-                    completed += 1;
-                } else {
-                    progress_item.status = InstallStatus::Failed;
-                    progress_item.progress = 100;
-                    progress_item.message = result.message.clone();
-                    logger.log(id, "install", "failed", &result.message, &result.message);
-                    failed += 1;
-                }
+    let forward_progress = async {
+        let events = operation.stream();
+        futures::pin_mut!(events);
+        while let Some(event) = events.next().await {
+            if let OperationEvent::Progress { progress } = event {
+                batch.update(progress);
             }
+        }
+    };
+    let (result, _) = tokio::join!(
+        execute_with_prerequisites(state, installer, Arc::clone(&operation), prerequisites),
+        forward_progress,
+    );
+
+    batch.update(operation.info().progress);
+    done.send_replace(Some(result.success));
+}
+
+async fn run_batch_install(state: &AppState, batch: &Batch, ids: &[String], concurrency: usize) {
+    let semaphore = Semaphore::new(concurrency);
+
+    let mut senders = HashMap::new();
+    let mut receivers = HashMap::new();
+    for id in ids {
+        let (tx, rx) = watch::channel(None);
+        senders.insert(id.clone(), tx);
+        receivers.insert(id.clone(), rx);
+    }
+
+    let mut items = Vec::new();
+    for id in ids {
+        let done = senders.remove(id).unwrap();
+
+        let Some(installer) = find_installer(state, id) else {
+            state.logger.log(id, "install", "failed", "Установщик не найден", "");
+            batch.update(failed_progress(id, "Установщик не найден"));
+            done.send_replace(Some(false));
+            continue;
+        };
+
+        let mut plan = match install_plan(state, std::slice::from_ref(id)) {
+            Ok(plan) => plan,
             Err(e) => {
-                progress_item.status = InstallStatus::Failed;
-                progress_item.progress = 100;
-                let error_msg = format!("Ошибка: {}", e);
-                progress_item.message = error_msg.clone();
-                logger.log(id, "install", "failed", &error_msg, &error_msg);
-                failed += 1;
+                state.logger.log(id, "install", "failed", &e.to_string(), "");
+                batch.update(failed_progress(id, &e.to_string()));
+                done.send_replace(Some(false));
+                continue;
             }
+        };
+        plan.retain(|i| i.id() != id);
+
+        // Запрошенные в том же пакете зависимости устанавливает их собственный элемент,
+        // этот лишь дожидается его результата и проверяет требования к версии.
+        let mut dependents = plan.clone();
+        dependents.push(Arc::clone(&installer));
+        let waits = plan
+            .iter()
+            .filter_map(|p| {
+                let receiver = receivers.get(p.id())?.clone();
+                Some(BatchWait {
+                    dependency: Arc::clone(p),
+                    requirements: version_requirements(p.id(), &dependents),
+                    done: receiver,
+                })
+            })
+            .collect();
+        plan.retain(|p| !receivers.contains_key(p.id()));
+
+        items.push(batch_install_item(
+            state, batch, installer, plan, waits, &semaphore, done,
+        ));
+    }
+
+    future::join_all(items).await;
+    batch.finish();
+}
+
+async fn batch_install(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    axum::Json(request): axum::Json<BatchInstallRequest>,
+) -> Result<(StatusCode, Json<BatchInfo>), StatusCode> {
+    let mut ids: Vec<String> = Vec::new();
+    for id in request.ids {
+        if !ids.contains(&id) {
+            ids.push(id);
         }
     }
+    let concurrency = request.concurrency.unwrap_or(DEFAULT_BATCH_CONCURRENCY).max(1);
+
+    let batch = state.batches.create(&ids);
+    let info = batch.info();
+
+    tokio::spawn(async move {
+        run_batch_install(&state, &batch, &ids, concurrency).await;
+    });
 
-    Ok(Json(BatchInstallResponse {
-        total,
-        completed,
-        failed,
-        progress,
-    }))
+    Ok((StatusCode::ACCEPTED, Json(info)))
+}
+
+async fn get_batch(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    Path(batch_id): Path<String>,
+) -> Result<Json<BatchInfo>, ApiError> {
+    state
+        .batches
+        .get(&batch_id)
+        .map(|batch| Json(batch.info()))
+        .ok_or(ApiError::BatchNotFound(batch_id))
+}
+
+async fn stream_batch(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    Path(batch_id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let batch = state.batches.get(&batch_id).ok_or(ApiError::BatchNotFound(batch_id))?;
+    let events = batch.stream().map(|event| {
+        Ok(Event::default()
+            .event(event.name())
+            .json_data(&event)
+            .unwrap_or_default())
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
 }
 
 async fn get_categories(
@@ -326,3 +859,13 @@ async fn get_categories(
     
     Ok(Json(categories_vec))
 }
+
+async fn get_catalog_diagnostics(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+) -> Json<CatalogDiagnostics> {
+    Json(state.loader.diagnostics())
+}
+
+async fn get_manifest_schema() -> Json<&'static serde_json::Value> {
+    Json(schema::manifest_schema())
+}
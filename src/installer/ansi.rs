@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+
+const ESC: char = '\x1b';
+const BEL: char = '\x07';
+
+// Палитра 16 базовых цветов терминала, индексы 0–15 в SGR.
+const PALETTE: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0xcd, 0x31, 0x31),
+    (0x0d, 0xbc, 0x79),
+    (0xe5, 0xe5, 0x10),
+    (0x24, 0x72, 0xc8),
+    (0xbc, 0x3f, 0xbc),
+    (0x11, 0xa8, 0xcd),
+    (0xe5, 0xe5, 0xe5),
+    (0x66, 0x66, 0x66),
+    (0xf1, 0x4c, 0x4c),
+    (0x23, 0xd1, 0x8b),
+    (0xf5, 0xf5, 0x43),
+    (0x3b, 0x8e, 0xea),
+    (0xd6, 0x70, 0xd6),
+    (0x29, 0xb8, 0xdb),
+    (0xff, 0xff, 0xff),
+];
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Color {
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    fn css(self) -> String {
+        let (r, g, b) = match self {
+            Color::Indexed(index @ 0..=15) => PALETTE[index as usize],
+            Color::Indexed(index @ 16..=231) => {
+                let index = index - 16;
+                (
+                    CUBE_LEVELS[(index / 36) as usize],
+                    CUBE_LEVELS[(index / 6 % 6) as usize],
+                    CUBE_LEVELS[(index % 6) as usize],
+                )
+            }
+            Color::Indexed(index) => {
+                let level = 8 + (index - 232) * 10;
+                (level, level, level)
+            }
+            Color::Rgb(r, g, b) => (r, g, b),
+        };
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Style {
+    fg: Option<Color>,
+    bg: Option<Color>,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+}
+
+impl Style {
+    fn apply_sgr(&mut self, params: &[u16]) {
+        if params.is_empty() {
+            *self = Style::default();
+            return;
+        }
+        let mut params = params.iter().copied();
+        while let Some(param) = params.next() {
+            match param {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                21 | 22 => self.bold = false,
+                23 => self.italic = false,
+                24 => self.underline = false,
+                30..=37 => self.fg = Some(Color::Indexed((param - 30) as u8)),
+                38 => self.fg = extended_color(&mut params),
+                39 => self.fg = None,
+                40..=47 => self.bg = Some(Color::Indexed((param - 40) as u8)),
+                48 => self.bg = extended_color(&mut params),
+                49 => self.bg = None,
+                90..=97 => self.fg = Some(Color::Indexed((param - 90 + 8) as u8)),
+                100..=107 => self.bg = Some(Color::Indexed((param - 100 + 8) as u8)),
+                _ => {}
+            }
+        }
+    }
+}
+
+// `38;5;n` — цвет из палитры 256 цветов, `38;2;r;g;b` — true color.
+fn extended_color(params: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    match params.next()? {
+        5 => Some(Color::Indexed(params.next()?.min(255) as u8)),
+        2 => {
+            let mut channel = || params.next().map(|c| c.min(255) as u8);
+            Some(Color::Rgb(channel()?, channel()?, channel()?))
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StyledSpan {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fg: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bg: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bold: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub italic: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub underline: bool,
+}
+
+impl StyledSpan {
+    fn new(text: String, style: Style) -> Self {
+        Self {
+            text,
+            fg: style.fg.map(Color::css),
+            bg: style.bg.map(Color::css),
+            bold: style.bold,
+            italic: style.italic,
+            underline: style.underline,
+        }
+    }
+
+    fn is_plain(&self) -> bool {
+        self.fg.is_none() && self.bg.is_none() && !self.bold && !self.italic && !self.underline
+    }
+
+    fn same_style(&self, other: &StyledSpan) -> bool {
+        self.fg == other.fg
+            && self.bg == other.bg
+            && self.bold == other.bold
+            && self.italic == other.italic
+            && self.underline == other.underline
+    }
+}
+
+// Строка вывода в том виде, в каком её показал бы терминал: текст без
+// управляющих последовательностей и, если было оформление, разбивка на спаны.
+#[derive(Debug, Clone, Default)]
+pub struct RenderedLine {
+    pub text: String,
+    pub spans: Vec<StyledSpan>,
+}
+
+impl RenderedLine {
+    pub fn plain(text: String) -> Self {
+        let mut spans = Vec::new();
+        push_span(&mut spans, &text, Style::default());
+        Self { text, spans }
+    }
+
+    pub fn is_styled(&self) -> bool {
+        self.spans.iter().any(|span| !span.is_plain())
+    }
+}
+
+// Разбирает одну строку (без `\n`). `\r` и `\x1b[G` возвращают курсор,
+// последующие символы затирают уже выведенные, как в терминале, — так
+// от прогресс-баров остаётся только последнее состояние. `\x1b[K`
+// стирает строку, SGR задаёт оформление, остальные последовательности
+// отбрасываются.
+pub fn render_line(raw: &str) -> RenderedLine {
+    let mut cells: Vec<(char, Style)> = Vec::new();
+    let mut cursor: usize = 0;
+    let mut style = Style::default();
+    let mut chars = raw.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' => cursor = 0,
+            '\x08' => cursor = cursor.saturating_sub(1),
+            ESC => match chars.next() {
+                Some('[') => {
+                    let mut body = String::new();
+                    let mut command = None;
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            command = Some(c);
+                            break;
+                        }
+                        body.push(c);
+                    }
+                    let params: Vec<u16> = body
+                        .split(';')
+                        .filter(|p| !p.is_empty())
+                        .filter_map(|p| p.parse().ok())
+                        .collect();
+                    match command {
+                        Some('m') => style.apply_sgr(&params),
+                        Some('K') => match params.first().copied().unwrap_or(0) {
+                            0 => cells.truncate(cursor),
+                            1 => cells.iter_mut().take(cursor).for_each(|cell| *cell = (' ', Style::default())),
+                            _ => {
+                                cells.clear();
+                                cursor = 0;
+                            }
+                        },
+                        Some('G') => cursor = params.first().map(|&col| col.saturating_sub(1) as usize).unwrap_or(0),
+                        _ => {}
+                    }
+                }
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == BEL || (c == ESC && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                Some('(' | ')') => {
+                    chars.next();
+                }
+                _ => {}
+            },
+            c if c.is_control() && c != '\t' => {}
+            c => {
+                if cursor < cells.len() {
+                    cells[cursor] = (c, style);
+                } else {
+                    cells.resize(cursor, (' ', Style::default()));
+                    cells.push((c, style));
+                }
+                cursor += 1;
+            }
+        }
+    }
+
+    let mut line = RenderedLine::default();
+    for (c, style) in cells {
+        line.text.push(c);
+        push_span(&mut line.spans, &c.to_string(), style);
+    }
+    line
+}
+
+fn push_span(spans: &mut Vec<StyledSpan>, text: &str, style: Style) {
+    if text.is_empty() {
+        return;
+    }
+    let span = StyledSpan::new(text.to_string(), style);
+    match spans.last_mut() {
+        Some(last) if last.same_style(&span) => last.text.push_str(text),
+        _ => spans.push(span),
+    }
+}
+
+// Собирает спаны нескольких строк в один список; `newline` добавляет
+// разделитель строк.
+pub fn append_line(spans: &mut Vec<StyledSpan>, line: RenderedLine, newline: bool) {
+    for span in line.spans {
+        match spans.last_mut() {
+            Some(last) if last.same_style(&span) => last.text.push_str(&span.text),
+            _ => spans.push(span),
+        }
+    }
+    if newline {
+        push_span(spans, "\n", Style::default());
+    }
+}
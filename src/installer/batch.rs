@@ -0,0 +1,210 @@
+use crate::installer::{BatchInstallResponse, InstallProgress, InstallStatus};
+use chrono::Utc;
+use futures::stream::{self, Stream, StreamExt};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+const MAX_BATCHES: usize = 50;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchInfo {
+    pub id: String,
+    pub created_at: String,
+    pub finished_at: Option<String>,
+    #[serde(flatten)]
+    pub summary: BatchInstallResponse,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BatchEvent {
+    Snapshot { batch: BatchInfo },
+    Progress { progress: InstallProgress },
+    Finished { batch: BatchInfo },
+}
+
+impl BatchEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BatchEvent::Snapshot { .. } => "snapshot",
+            BatchEvent::Progress { .. } => "progress",
+            BatchEvent::Finished { .. } => "finished",
+        }
+    }
+}
+
+struct BatchState {
+    items: Vec<InstallProgress>,
+    finished_at: Option<String>,
+}
+
+pub struct Batch {
+    id: String,
+    created_at: String,
+    state: Mutex<BatchState>,
+    tx: broadcast::Sender<BatchEvent>,
+}
+
+impl Batch {
+    fn new(ids: &[String]) -> Self {
+        let (tx, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        let items = ids
+            .iter()
+            .map(|id| InstallProgress {
+                id: id.clone(),
+                status: InstallStatus::Pending,
+                progress: 0,
+                message: "Ожидание...".to_string(),
+            })
+            .collect();
+
+        Self {
+            id: Uuid::new_v4().to_string(),
+            created_at: Utc::now().to_rfc3339(),
+            state: Mutex::new(BatchState {
+                items,
+                finished_at: None,
+            }),
+            tx,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn snapshot(&self, state: &BatchState) -> BatchInfo {
+        let total = state.items.len();
+        let completed = state
+            .items
+            .iter()
+            .filter(|p| p.status == InstallStatus::Completed)
+            .count();
+        let failed = state
+            .items
+            .iter()
+            .filter(|p| matches!(p.status, InstallStatus::Failed | InstallStatus::Cancelled))
+            .count();
+
+        BatchInfo {
+            id: self.id.clone(),
+            created_at: self.created_at.clone(),
+            finished_at: state.finished_at.clone(),
+            summary: BatchInstallResponse {
+                total,
+                completed,
+                failed,
+                progress: state.items.clone(),
+            },
+        }
+    }
+
+    pub fn info(&self) -> BatchInfo {
+        let state = self.state.lock().unwrap();
+        self.snapshot(&state)
+    }
+
+    pub fn update(&self, progress: InstallProgress) {
+        let mut state = self.state.lock().unwrap();
+        if state.finished_at.is_some() {
+            return;
+        }
+        let Some(item) = state.items.iter_mut().find(|p| p.id == progress.id) else {
+            return;
+        };
+        *item = progress.clone();
+        let _ = self.tx.send(BatchEvent::Progress { progress });
+    }
+
+    pub fn finish(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.finished_at.is_some() {
+            return;
+        }
+        state.finished_at = Some(Utc::now().to_rfc3339());
+        let _ = self.tx.send(BatchEvent::Finished {
+            batch: self.snapshot(&state),
+        });
+    }
+
+    pub fn stream(&self) -> impl Stream<Item = BatchEvent> {
+        let (snapshot, receiver) = {
+            let state = self.state.lock().unwrap();
+            (self.snapshot(&state), self.tx.subscribe())
+        };
+        let finished = snapshot.finished_at.is_some();
+
+        let live = stream::unfold((receiver, finished), |(mut receiver, finished)| async move {
+            if finished {
+                return None;
+            }
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        let finished = matches!(event, BatchEvent::Finished { .. });
+                        return Some((event, (receiver, finished)));
+                    }
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+
+        stream::iter([BatchEvent::Snapshot { batch: snapshot }]).chain(live)
+    }
+}
+
+pub struct BatchManager {
+    batches: RwLock<HashMap<String, Arc<Batch>>>,
+    order: RwLock<Vec<String>>,
+}
+
+impl BatchManager {
+    pub fn new() -> Self {
+        Self {
+            batches: RwLock::new(HashMap::new()),
+            order: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn create(&self, ids: &[String]) -> Arc<Batch> {
+        let batch = Arc::new(Batch::new(ids));
+        let mut batches = self.batches.write().unwrap();
+        let mut order = self.order.write().unwrap();
+        batches.insert(batch.id().to_string(), Arc::clone(&batch));
+        order.push(batch.id().to_string());
+
+        while order.len() > MAX_BATCHES {
+            let finished = order.iter().position(|id| {
+                batches
+                    .get(id)
+                    .map(|b| b.state.lock().unwrap().finished_at.is_some())
+                    .unwrap_or(true)
+            });
+            match finished {
+                Some(index) => {
+                    let id = order.remove(index);
+                    batches.remove(&id);
+                }
+                None => break,
+            }
+        }
+
+        batch
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<Batch>> {
+        let batches = self.batches.read().unwrap();
+        batches.get(id).map(Arc::clone)
+    }
+}
+
+impl Default for BatchManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
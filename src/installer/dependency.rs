@@ -0,0 +1,142 @@
+use crate::installer::Installer;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MissingDependency {
+    pub id: String,
+    pub required_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DependencyError {
+    Missing { missing: Vec<MissingDependency> },
+    Cycle { cycle: Vec<String> },
+}
+
+impl fmt::Display for DependencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DependencyError::Missing { missing } => {
+                let list: Vec<String> = missing
+                    .iter()
+                    .map(|m| match &m.required_by {
+                        Some(parent) => format!("{} (требуется для {})", m.id, parent),
+                        None => m.id.clone(),
+                    })
+                    .collect();
+                write!(f, "Не найдены зависимости: {}", list.join(", "))
+            }
+            DependencyError::Cycle { cycle } => {
+                write!(f, "Циклическая зависимость: {}", cycle.join(" -> "))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mark {
+    Visiting,
+    Done,
+}
+
+struct Resolver<'a, F> {
+    lookup: &'a F,
+    marks: HashMap<String, Mark>,
+    stack: Vec<String>,
+    order: Vec<Arc<dyn Installer>>,
+    missing: Vec<MissingDependency>,
+}
+
+impl<F> Resolver<'_, F>
+where
+    F: Fn(&str) -> Option<Arc<dyn Installer>>,
+{
+    fn visit(&mut self, id: &str) -> Result<(), DependencyError> {
+        match self.marks.get(id) {
+            Some(Mark::Done) => return Ok(()),
+            Some(Mark::Visiting) => {
+                let start = self.stack.iter().position(|s| s == id).unwrap_or(0);
+                let mut cycle = self.stack[start..].to_vec();
+                cycle.push(id.to_string());
+                return Err(DependencyError::Cycle { cycle });
+            }
+            None => {}
+        }
+
+        let Some(installer) = (self.lookup)(id) else {
+            if !self.missing.iter().any(|m| m.id == id) {
+                self.missing.push(MissingDependency {
+                    id: id.to_string(),
+                    required_by: self.stack.last().cloned(),
+                });
+            }
+            self.marks.insert(id.to_string(), Mark::Done);
+            return Ok(());
+        };
+
+        self.marks.insert(id.to_string(), Mark::Visiting);
+        self.stack.push(id.to_string());
+        for dependency in installer.dependencies() {
+            self.visit(&dependency)?;
+        }
+        self.stack.pop();
+        self.marks.insert(id.to_string(), Mark::Done);
+        self.order.push(installer);
+        Ok(())
+    }
+}
+
+// Возвращает установщики в порядке установки: каждая зависимость
+// идёт раньше зависящего от неё приложения, корни — после своих зависимостей.
+pub fn resolve_install_order<F>(roots: &[String], lookup: F) -> Result<Vec<Arc<dyn Installer>>, DependencyError>
+where
+    F: Fn(&str) -> Option<Arc<dyn Installer>>,
+{
+    let mut resolver = Resolver {
+        lookup: &lookup,
+        marks: HashMap::new(),
+        stack: Vec::new(),
+        order: Vec::new(),
+        missing: Vec::new(),
+    };
+
+    for root in roots {
+        resolver.visit(root)?;
+    }
+
+    if !resolver.missing.is_empty() {
+        return Err(DependencyError::Missing {
+            missing: resolver.missing,
+        });
+    }
+
+    Ok(resolver.order)
+}
+
+// Все установщики каталога, прямо или транзитивно зависящие от `target`,
+// в порядке удаления: зависимые приложения идут раньше своих зависимостей.
+pub fn dependents_uninstall_order(target: &str, catalog: &[Arc<dyn Installer>]) -> Vec<Arc<dyn Installer>> {
+    fn visit(
+        id: &str,
+        catalog: &[Arc<dyn Installer>],
+        visited: &mut HashSet<String>,
+        order: &mut Vec<Arc<dyn Installer>>,
+    ) {
+        visited.insert(id.to_string());
+        for dependent in catalog.iter().filter(|i| i.dependencies().iter().any(|d| d == id)) {
+            if !visited.contains(dependent.id()) {
+                visit(dependent.id(), catalog, visited, order);
+                order.push(Arc::clone(dependent));
+            }
+        }
+    }
+
+    let mut visited = HashSet::new();
+    let mut order = Vec::new();
+    visit(target, catalog, &mut visited, &mut order);
+    order
+}
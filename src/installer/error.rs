@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum InstallerError {
+    #[error("Скрипт для действия {action} не задан")]
+    ScriptNotConfigured { action: String },
+
+    #[error("Скрипт не существует: {}", .script.display())]
+    ScriptNotFound { script: PathBuf },
+
+    #[error("Не удалось запустить {command}: {message}")]
+    SpawnFailed {
+        command: String,
+        #[serde(default)]
+        script: Option<PathBuf>,
+        message: String,
+    },
+
+    #[error("Команда завершилась с ошибкой{}: {stderr_tail}", exit_code_suffix(.exit_code))]
+    NonZeroExit {
+        #[serde(default)]
+        script: Option<PathBuf>,
+        exit_code: Option<i32>,
+        stderr_tail: String,
+    },
+
+    #[error("Превышено время выполнения ({timeout_secs} с)")]
+    Timeout {
+        #[serde(default)]
+        script: Option<PathBuf>,
+        timeout_secs: u64,
+    },
+
+    #[error("Операция отменена")]
+    Cancelled,
+
+    #[error("{message}")]
+    DependencyFailed { dependency: String, message: String },
+
+    #[error("Ошибка парсинга конфигурации {}: {message}", .path.display())]
+    InvalidConfig {
+        path: PathBuf,
+        message: String,
+        #[serde(default)]
+        line: Option<usize>,
+        #[serde(default)]
+        column: Option<usize>,
+    },
+
+    #[error("Ошибка ввода-вывода {}: {message}", .path.display())]
+    Io { path: PathBuf, message: String },
+}
+
+fn exit_code_suffix(exit_code: &Option<i32>) -> String {
+    match exit_code {
+        Some(code) => format!(" (код {})", code),
+        None => " (процесс завершён сигналом)".to_string(),
+    }
+}
+
+impl InstallerError {
+    pub fn io(path: impl Into<PathBuf>, error: std::io::Error) -> Self {
+        InstallerError::Io {
+            path: path.into(),
+            message: error.to_string(),
+        }
+    }
+
+    // Ошибки запуска команды не знают, какой скрипт она выполняла, —
+    // путь дописывает вызывающий код.
+    pub fn with_script(mut self, path: PathBuf) -> Self {
+        match &mut self {
+            InstallerError::SpawnFailed { script, .. }
+            | InstallerError::NonZeroExit { script, .. }
+            | InstallerError::Timeout { script, .. } => *script = Some(path),
+            _ => {}
+        }
+        self
+    }
+}
@@ -0,0 +1,228 @@
+use crate::installer::logger::LogRecord;
+use flate2::{write::GzEncoder, Compression};
+use serde::Deserialize;
+use std::io::Write;
+
+const CSV_COLUMNS: [&str; 12] = [
+    "timestamp",
+    "installer_id",
+    "run_id",
+    "action",
+    "status",
+    "progress",
+    "exit_code",
+    "duration_ms",
+    "message",
+    "output",
+    "stdout",
+    "stderr",
+];
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Jsonl,
+    Csv,
+    Text,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Jsonl => "application/x-ndjson",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Text => "text/plain; charset=utf-8",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Text => "txt",
+        }
+    }
+
+    fn header(&self) -> Option<String> {
+        match self {
+            ExportFormat::Csv => Some(format!("{}\r\n", CSV_COLUMNS.join(","))),
+            ExportFormat::Jsonl | ExportFormat::Text => None,
+        }
+    }
+
+    fn render(&self, record: &LogRecord) -> String {
+        match self {
+            ExportFormat::Jsonl => {
+                let mut line = serde_json::to_string(record).unwrap_or_default();
+                line.push('\n');
+                line
+            }
+            ExportFormat::Csv => render_csv(record),
+            ExportFormat::Text => render_text(record),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExportOptions {
+    #[serde(default)]
+    pub format: ExportFormat,
+    #[serde(default)]
+    pub gzip: bool,
+}
+
+impl ExportOptions {
+    pub fn content_type(&self) -> &'static str {
+        if self.gzip {
+            "application/gzip"
+        } else {
+            self.format.content_type()
+        }
+    }
+
+    pub fn file_name(&self, scope: &str) -> String {
+        let scope: String = scope
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        let name = format!("{}.{}", scope, self.format.extension());
+        if self.gzip {
+            format!("{}.gz", name)
+        } else {
+            name
+        }
+    }
+}
+
+// Формирует выгрузку по частям, чтобы её можно было отдавать потоком:
+// `write` возвращает готовые байты очередной порции, `finish` — хвост
+// (для gzip — завершающий блок архива).
+pub struct LogExporter {
+    format: ExportFormat,
+    encoder: Option<GzEncoder<Vec<u8>>>,
+    started: bool,
+}
+
+impl LogExporter {
+    pub fn new(options: &ExportOptions) -> Self {
+        Self {
+            format: options.format,
+            encoder: options.gzip.then(|| GzEncoder::new(Vec::new(), Compression::default())),
+            started: false,
+        }
+    }
+
+    pub fn write(&mut self, records: &[LogRecord]) -> Vec<u8> {
+        let mut chunk = String::new();
+        if !self.started {
+            self.started = true;
+            chunk.extend(self.format.header());
+        }
+        for record in records {
+            chunk.push_str(&self.format.render(record));
+        }
+        self.emit(chunk.as_bytes())
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let mut tail = self.write(&[]);
+        if let Some(encoder) = self.encoder.take() {
+            tail.extend(encoder.finish().unwrap_or_default());
+        }
+        tail
+    }
+
+    fn emit(&mut self, bytes: &[u8]) -> Vec<u8> {
+        match &mut self.encoder {
+            Some(encoder) => {
+                let _ = encoder.write_all(bytes);
+                std::mem::take(encoder.get_mut())
+            }
+            None => bytes.to_vec(),
+        }
+    }
+}
+
+fn render_csv(record: &LogRecord) -> String {
+    let entry = &record.entry;
+    let execution = entry.execution.as_ref();
+    let fields = [
+        entry.timestamp.clone(),
+        record.installer_id.clone(),
+        entry.run_id.clone().unwrap_or_default(),
+        entry.action.clone(),
+        entry.status.clone(),
+        entry.progress.map(|p| p.to_string()).unwrap_or_default(),
+        execution.and_then(|e| e.exit_code).map(|c| c.to_string()).unwrap_or_default(),
+        execution.map(|e| e.duration_ms.to_string()).unwrap_or_default(),
+        entry.message.clone(),
+        entry.output.clone(),
+        execution.map(|e| e.stdout.clone()).unwrap_or_default(),
+        execution.map(|e| e.stderr.clone()).unwrap_or_default(),
+    ];
+    let mut line = fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(",");
+    line.push_str("\r\n");
+    line
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Текстовая расшифровка для чтения человеком: строка-заголовок записи и
+// её сообщение, вывод и итог запуска с отступом.
+fn render_text(record: &LogRecord) -> String {
+    let entry = &record.entry;
+    let mut text = format!(
+        "[{}] {} {} {}",
+        entry.timestamp, record.installer_id, entry.action, entry.status
+    );
+    if let Some(progress) = entry.progress {
+        text.push_str(&format!(" {}%", progress));
+    }
+    if let Some(run_id) = &entry.run_id {
+        text.push_str(&format!(" (запуск {})", run_id));
+    }
+    text.push('\n');
+
+    push_block(&mut text, None, &entry.message);
+    if entry.output != entry.message {
+        push_block(&mut text, Some("вывод"), &entry.output);
+    }
+    if let Some(execution) = &entry.execution {
+        push_block(&mut text, Some("stdout"), &execution.stdout);
+        push_block(&mut text, Some("stderr"), &execution.stderr);
+        let outcome = match (execution.exit_code, execution.signal) {
+            (Some(code), _) => format!("код завершения {}", code),
+            (None, Some(signal)) => format!("завершён сигналом {}", signal),
+            (None, None) => "код завершения неизвестен".to_string(),
+        };
+        let script = execution.script.as_deref().unwrap_or("-");
+        text.push_str(&format!("    {}: {}, {} мс\n", script, outcome, execution.duration_ms));
+    }
+    if let Some(overflow) = &entry.overflow {
+        text.push_str(&format!("    полный текст: {}\n", overflow));
+    }
+    text.push('\n');
+    text
+}
+
+fn push_block(text: &mut String, title: Option<&str>, body: &str) {
+    let body = body.trim_end();
+    if body.is_empty() {
+        return;
+    }
+    if let Some(title) = title {
+        text.push_str(&format!("    --- {} ---\n", title));
+    }
+    for line in body.lines() {
+        text.push_str("    ");
+        text.push_str(line);
+        text.push('\n');
+    }
+}
@@ -1,77 +1,309 @@
-use crate::installer::{Installer, script::{ScriptInstaller, ScriptInstallerConfig}};
-use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use crate::installer::{Installer, InstallerError, script::{ScriptInstaller, ScriptInstallerConfig}};
+use crate::installer::schema::validate_manifest;
+use crate::installer::validation::{check_requirement, check_scripts, validate_config, Diagnostic};
+use notify::{Config, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use std::fs;
 use serde_json;
-use tokio::time::{interval, Duration};
-use tokio::sync::broadcast;
+use sha2::{Digest, Sha256};
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{timeout_at, Duration, Instant};
+
+// Изменения копятся, пока файлы меняются чаще DEBOUNCE_QUIET, но не дольше
+// DEBOUNCE_MAX, — сохранение редактором или git checkout дают одну перезагрузку.
+const DEBOUNCE_QUIET: Duration = Duration::from_millis(300);
+const DEBOUNCE_MAX: Duration = Duration::from_secs(2);
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// Короткий хеш содержимого манифеста: по нему в логах видно,
+// с какой редакцией конфигурации выполнялся скрипт.
+fn manifest_revision(content: &str) -> String {
+    let digest = format!("{:x}", Sha256::digest(content.as_bytes()));
+    digest[..12].to_string()
+}
+
+// Манифест самодостаточного установщика: `installers/<id>/manifest.json`
+// со скриптами рядом.
+const BUNDLE_MANIFEST: &str = "manifest";
+
+// Порядок вариантов задаёт приоритет: если один ID объявлен в манифестах
+// разных форматов, загружается JSON, затем TOML, затем YAML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ManifestFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ManifestFormat {
+    const EXTENSIONS: [&'static str; 4] = ["json", "toml", "yaml", "yml"];
+
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "json" => Some(ManifestFormat::Json),
+            "toml" => Some(ManifestFormat::Toml),
+            "yaml" | "yml" => Some(ManifestFormat::Yaml),
+            _ => None,
+        }
+    }
+
+    // Любой формат сначала приводится к JSON-значению, чтобы проверка по
+    // схеме и десериализация были общими.
+    fn parse(self, path: &Path, content: &str) -> Result<serde_json::Value, InstallerError> {
+        let invalid = |message: String, position: Option<(usize, usize)>| InstallerError::InvalidConfig {
+            path: path.to_path_buf(),
+            message,
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, column)| column),
+        };
+        match self {
+            ManifestFormat::Json => serde_json::from_str(content)
+                .map_err(|e| invalid(e.to_string(), Some((e.line(), e.column())))),
+            ManifestFormat::Toml => toml::from_str(content).map_err(|e| {
+                let position = e.span().map(|span| line_column(content, span.start));
+                invalid(e.message().to_string(), position)
+            }),
+            ManifestFormat::Yaml => serde_yaml::from_str(content).map_err(|e| {
+                let position = e.location().map(|location| (location.line(), location.column()));
+                invalid(e.to_string(), position)
+            }),
+        }
+    }
+}
+
+// Категория по умолчанию из пути к папке: `dev-tools/editors` → `Dev Tools / Editors`.
+fn folder_category(folder: &Path) -> Option<String> {
+    let segments: Vec<String> = folder
+        .iter()
+        .filter_map(|segment| segment.to_str())
+        .map(|segment| {
+            segment
+                .split(['-', '_', ' '])
+                .filter(|word| !word.is_empty())
+                .map(|word| {
+                    let mut chars = word.chars();
+                    chars.next().map(|c| c.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    (!segments.is_empty()).then(|| segments.join(" / "))
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.'))
+}
+
+// Строка и столбец (с единицы) для смещения в байтах.
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CatalogChange {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl CatalogChange {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileDiagnostics {
+    pub path: PathBuf,
+    pub id: Option<String>,
+    pub loaded: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CatalogDiagnostics {
+    pub checked: usize,
+    pub errors: usize,
+    pub warnings: usize,
+    pub files: Vec<FileDiagnostics>,
+}
+
+// Манифест хранится и тогда, когда установщик из него не загружен, —
+// чтобы сообщить о причине в диагностике.
+struct Manifest {
+    id: Option<String>,
+    installer: Option<Arc<ScriptInstaller>>,
+    revision: Option<String>,
+    scripts: Vec<(&'static str, PathBuf)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Manifest {
+    fn new(installer: ScriptInstaller) -> Self {
+        let scripts = installer
+            .script_files()
+            .into_iter()
+            .map(|(action, path)| (action, normalize(&path)))
+            .collect();
+        let diagnostics = validate_config(installer.config());
+        let id = Some(installer.id().to_string());
+        let revision = Some(installer.revision().to_string());
+        let installer = (!diagnostics.iter().any(Diagnostic::is_error)).then(|| Arc::new(installer));
+        Self {
+            id,
+            installer,
+            revision,
+            scripts,
+            diagnostics,
+        }
+    }
+
+    fn failed(id: Option<String>, diagnostics: Vec<Diagnostic>) -> Self {
+        Self {
+            id,
+            installer: None,
+            revision: None,
+            scripts: Vec::new(),
+            diagnostics,
+        }
+    }
+}
 
 pub struct InstallerLoader {
-    installers: Arc<RwLock<HashMap<String, Arc<dyn Installer>>>>,
+    installers: RwLock<HashMap<String, Arc<dyn Installer>>>,
+    manifests: RwLock<BTreeMap<PathBuf, Manifest>>,
+    diagnostics: RwLock<CatalogDiagnostics>,
+    builtin_ids: HashSet<String>,
     scripts_dir: PathBuf,
-    reload_tx: broadcast::Sender<()>,
+    reload_tx: broadcast::Sender<CatalogChange>,
 }
 
 impl InstallerLoader {
     pub fn new(scripts_dir: PathBuf) -> Self {
         let (tx, _) = broadcast::channel(16);
         Self {
-            installers: Arc::new(RwLock::new(HashMap::new())),
+            installers: RwLock::new(HashMap::new()),
+            manifests: RwLock::new(BTreeMap::new()),
+            diagnostics: RwLock::new(CatalogDiagnostics::default()),
+            builtin_ids: HashSet::new(),
             scripts_dir,
             reload_tx: tx,
         }
     }
 
+    // ID встроенных установщиков: зависимости от них допустимы, а манифест
+    // с таким же ID был бы ими перекрыт.
+    pub fn with_builtin_ids(mut self, ids: impl IntoIterator<Item = String>) -> Self {
+        self.builtin_ids = ids.into_iter().collect();
+        self
+    }
+
+    pub fn diagnostics(&self) -> CatalogDiagnostics {
+        self.diagnostics.read().unwrap().clone()
+    }
+
     #[allow(dead_code)]
-    pub fn subscribe_reload(&self) -> broadcast::Receiver<()> {
+    pub fn subscribe_reload(&self) -> broadcast::Receiver<CatalogChange> {
         self.reload_tx.subscribe()
     }
 
-    pub async fn load_all(&self) -> Result<(), String> {
-        let mut loaded = HashMap::new();
+    pub async fn load_all(&self) -> Result<(), InstallerError> {
+        let mut loaded = BTreeMap::new();
 
         if !self.scripts_dir.exists() {
             fs::create_dir_all(&self.scripts_dir)
-                .map_err(|e| format!("Не удалось создать директорию скриптов: {}", e))?;
+                .map_err(|e| InstallerError::io(&self.scripts_dir, e))?;
         }
+        // Недоступный корень — ошибка загрузки, а не пустой каталог.
+        fs::read_dir(&self.scripts_dir)
+            .map_err(|e| InstallerError::io(&self.scripts_dir, e))?;
 
-        let entries = fs::read_dir(&self.scripts_dir)
-            .map_err(|e| format!("Не удалось прочитать директорию скриптов: {}", e))?;
-
-        for entry in entries {
-            let entry = entry.map_err(|e| format!("Ошибка чтения файла: {}", e))?;
-            let path = entry.path();
-
-            if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("json") {
-                if let Ok(installer) = self.load_installer_from_file(&path).await {
-                    loaded.insert(installer.id().to_string(), Arc::new(installer) as Arc<dyn Installer>);
-                }
-            }
+        for path in self.find_manifests(&normalize(&self.scripts_dir)) {
+            let manifest = self.load_manifest(&path).await;
+            loaded.insert(path, manifest);
         }
 
-        let mut installers = self.installers.write().unwrap();
-        *installers = loaded;
+        let touched = loaded
+            .values()
+            .filter_map(|m| m.installer.as_ref().map(|i| i.id().to_string()))
+            .collect();
+        *self.manifests.write().unwrap() = loaded;
+        self.rebuild(&touched);
         Ok(())
     }
 
-    async fn load_installer_from_file(&self, config_path: &Path) -> Result<ScriptInstaller, String> {
+    // Манифест проверяется по опубликованной схеме до десериализации,
+    // все нарушения возвращаются разом.
+    async fn load_installer_from_file(&self, config_path: &Path) -> Result<ScriptInstaller, (Option<String>, Vec<Diagnostic>)> {
+        let invalid = |message: String, line, column| InstallerError::InvalidConfig {
+            path: config_path.to_path_buf(),
+            message,
+            line,
+            column,
+        };
+        let fail = |error: InstallerError| (None, vec![Diagnostic::from(&error)]);
+
         let content = fs::read_to_string(config_path)
-            .map_err(|e| format!("Не удалось прочитать файл конфигурации: {}", e))?;
+            .map_err(|e| fail(InstallerError::io(config_path, e)))?;
+
+        let format = ManifestFormat::from_path(config_path)
+            .ok_or_else(|| fail(invalid("Неизвестный формат манифеста".to_string(), None, None)))?;
+        let value = format.parse(config_path, &content).map_err(fail)?;
 
-        let config: ScriptInstallerConfig = serde_json::from_str(&content)
-            .map_err(|e| format!("Ошибка парсинга JSON: {}", e))?;
+        let violations = validate_manifest(&value);
+        if !violations.is_empty() {
+            let id = value.get("id").and_then(|id| id.as_str()).map(str::to_string);
+            return Err((id, violations));
+        }
+
+        let mut config: ScriptInstallerConfig = serde_json::from_value(value)
+            .map_err(|e| fail(invalid(e.to_string(), None, None)))?;
+
+        let folder = config_path.parent()
+            .ok_or_else(|| fail(invalid("Не удалось получить родительскую директорию".to_string(), None, None)))?;
+
+        if config.category.is_empty() {
+            let category_folder = if self.is_bundle_manifest(config_path) { folder.parent() } else { Some(folder) };
+            let root = normalize(&self.scripts_dir);
+            config.category = category_folder
+                .and_then(|dir| dir.strip_prefix(&root).ok())
+                .and_then(folder_category)
+                .ok_or_else(|| {
+                    let diagnostic = Diagnostic::error("Категория не задана и не может быть выведена: манифест лежит в корне каталога")
+                        .with_field("category");
+                    (Some(config.id.clone()), vec![diagnostic])
+                })?;
+        }
 
-        let base_path = config_path.parent()
-            .ok_or_else(|| "Не удалось получить родительскую директорию".to_string())?
-            .to_path_buf();
+        // Скрипты обычного манифеста лежат в одноимённой папке рядом с ним,
+        // а при её отсутствии — рядом с самим манифестом.
+        let base_path = match config_path.file_stem().map(|stem| folder.join(stem)) {
+            Some(scripts) if !self.is_bundle_manifest(config_path) && scripts.is_dir() => scripts,
+            _ => folder.to_path_buf(),
+        };
 
-        Ok(ScriptInstaller::new(config, base_path))
+        Ok(ScriptInstaller::new(config, base_path, manifest_revision(&content)))
+    }
+
+    async fn load_manifest(&self, path: &Path) -> Manifest {
+        match self.load_installer_from_file(path).await {
+            Ok(installer) => Manifest::new(installer),
+            Err((id, diagnostics)) => Manifest::failed(id, diagnostics),
+        }
     }
 
     pub fn get_all(&self) -> Vec<Arc<dyn Installer>> {
         let installers = self.installers.read().unwrap();
-        installers.values().map(|i| Arc::clone(i)).collect()
+        installers.values().map(Arc::clone).collect()
     }
 
     pub fn get(&self, id: &str) -> Option<Arc<dyn Installer>> {
@@ -79,63 +311,348 @@ impl InstallerLoader {
         installers.get(id).map(Arc::clone)
     }
 
-    pub fn start_watcher(&self) {
-        let scripts_dir = self.scripts_dir.clone();
-        let installers = Arc::clone(&self.installers);
-        let reload_tx = self.reload_tx.clone();
+    fn is_bundle_manifest(&self, path: &Path) -> bool {
+        path.file_stem().and_then(|stem| stem.to_str()) == Some(BUNDLE_MANIFEST)
+            && path.parent() != Some(normalize(&self.scripts_dir).as_path())
+    }
 
-        tokio::spawn(async move {
-            let mut interval = interval(Duration::from_secs(2));
-            let mut last_modified = HashMap::new();
-
-            loop {
-                interval.tick().await;
-
-                if let Ok(entries) = fs::read_dir(&scripts_dir) {
-                    let mut changed = false;
-
-                    for entry in entries.flatten() {
-                        let path = entry.path();
-                        if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("json") {
-                            if let Ok(metadata) = fs::metadata(&path) {
-                                if let Ok(modified) = metadata.modified() {
-                                    let path_str = path.to_string_lossy().to_string();
-                                    if let Some(&last) = last_modified.get(&path_str) {
-                                        if modified > last {
-                                            changed = true;
-                                        }
-                                    } else {
-                                        changed = true;
-                                    }
-                                    last_modified.insert(path_str, modified);
-                                }
+    // Папка, принадлежащая одному установщику: с `manifest.*` внутри либо
+    // папка скриптов рядом с одноимённым манифестом. Такие папки не
+    // просматриваются в поисках других манифестов.
+    fn is_installer_dir(&self, dir: &Path) -> bool {
+        let Some(parent) = dir.parent() else {
+            return false;
+        };
+        if dir == normalize(&self.scripts_dir) {
+            return false;
+        }
+        let has_manifest = |dir: &Path, stem: &str| {
+            ManifestFormat::EXTENSIONS.iter().any(|ext| dir.join(format!("{}.{}", stem, ext)).is_file())
+        };
+        has_manifest(dir, BUNDLE_MANIFEST)
+            || dir.file_name().and_then(|name| name.to_str()).is_some_and(|name| has_manifest(parent, name))
+    }
+
+    fn is_manifest(&self, path: &Path) -> bool {
+        let root = normalize(&self.scripts_dir);
+        let (Some(dir), Ok(relative)) = (path.parent(), path.strip_prefix(&root)) else {
+            return false;
+        };
+        if ManifestFormat::from_path(path).is_none()
+            || relative.iter().any(|segment| segment.to_str().is_none_or(|s| s.starts_with('.')))
+        {
+            return false;
+        }
+        if !self.is_bundle_manifest(path) && self.is_installer_dir(dir) {
+            return false;
+        }
+        dir.ancestors()
+            .skip(1)
+            .take_while(|ancestor| ancestor.starts_with(&root) && *ancestor != root)
+            .all(|ancestor| !self.is_installer_dir(ancestor))
+    }
+
+    // Рекурсивный поиск манифестов: папки установщиков (см. `is_installer_dir`)
+    // дают только свой манифест, остальные папки служат для группировки.
+    fn find_manifests(&self, dir: &Path) -> Vec<PathBuf> {
+        let mut found = Vec::new();
+        let mut pending = vec![dir.to_path_buf()];
+        // Каталоги-ссылки обходятся, но каждый реальный каталог — один раз,
+        // иначе ссылка на родителя зациклит обход.
+        let mut visited = HashSet::new();
+        while let Some(dir) = pending.pop() {
+            let Ok(real) = fs::canonicalize(&dir) else {
+                continue;
+            };
+            if self.is_alias(&dir, &real) || !visited.insert(real) {
+                continue;
+            }
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = normalize(&entry.path());
+                if is_hidden(&path) {
+                    continue;
+                }
+                if path.is_dir() {
+                    pending.push(path);
+                } else if self.is_manifest(&path) {
+                    found.push(path);
+                }
+            }
+        }
+        found.sort();
+        found
+    }
+
+    // Ссылка на каталог внутри корня — лишь другой путь к тем же манифестам,
+    // их загружает обход по настоящему пути.
+    fn is_alias(&self, dir: &Path, real: &Path) -> bool {
+        let (Ok(root), Ok(relative)) = (fs::canonicalize(&self.scripts_dir), dir.strip_prefix(&self.scripts_dir)) else {
+            return false;
+        };
+        real.starts_with(&root) && real != root.join(relative)
+    }
+
+    // Перечитываются только затронутые манифесты; скрипты читаются при
+    // каждом запуске, поэтому их изменение лишь помечает установщики изменёнными.
+    async fn apply_changes(&self, paths: HashSet<PathBuf>) {
+        let mut touched = HashSet::new();
+        let mut rescan = BTreeSet::new();
+
+        for path in paths {
+            let real_parent = path.parent().and_then(|dir| Some((dir, fs::canonicalize(dir).ok()?)));
+            if real_parent.is_some_and(|(dir, real)| self.is_alias(dir, &real)) {
+                continue;
+            }
+            if self.is_manifest(&path) {
+                let manifest = match path.is_file() {
+                    true => Some(self.load_manifest(&path).await),
+                    false => None,
+                };
+                let mut manifests = self.manifests.write().unwrap();
+                match manifest {
+                    Some(manifest) => {
+                        let previous = manifests.get(&path).and_then(|m| m.revision.clone());
+                        if let Some(installer) = &manifest.installer {
+                            if previous != manifest.revision {
+                                touched.insert(installer.id().to_string());
                             }
                         }
+                        manifests.insert(path, manifest);
                     }
+                    None => {
+                        // Без манифеста папка становится обычной, и файлы в ней
+                        // могут оказаться самостоятельными манифестами.
+                        manifests.remove(&path);
+                        rescan.extend(path.parent().map(Path::to_path_buf));
+                    }
+                }
+                continue;
+            }
 
-                    if changed {
-                        let mut loaded = HashMap::new();
-                        if let Ok(entries) = fs::read_dir(&scripts_dir) {
-                            for entry in entries.flatten() {
-                                let path = entry.path();
-                                if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("json") {
-                                    if let Ok(content) = fs::read_to_string(&path) {
-                                        if let Ok(config) = serde_json::from_str::<crate::installer::script::ScriptInstallerConfig>(&content) {
-                                            let base_path = path.parent().unwrap().to_path_buf();
-                                            let installer = crate::installer::script::ScriptInstaller::new(config, base_path);
-                                            loaded.insert(installer.id().to_string(), Arc::new(installer) as Arc<dyn Installer>);
-                                        }
-                                    }
-                                }
-                            }
+            {
+                let manifests = self.manifests.read().unwrap();
+                for manifest in manifests.values() {
+                    let Some(installer) = &manifest.installer else {
+                        continue;
+                    };
+                    if manifest.scripts.iter().any(|(_, script)| script.starts_with(&path)) {
+                        touched.insert(installer.id().to_string());
+                    }
+                }
+            }
+
+            // Появившаяся папка может содержать ещё не загруженные манифесты.
+            if path.is_dir() {
+                rescan.insert(path);
+            }
+        }
+
+        for dir in rescan {
+            for manifest_path in self.find_manifests(&dir) {
+                if self.manifests.read().unwrap().contains_key(&manifest_path) {
+                    continue;
+                }
+                let manifest = self.load_manifest(&manifest_path).await;
+                if let Some(installer) = &manifest.installer {
+                    touched.insert(installer.id().to_string());
+                }
+                self.manifests.write().unwrap().insert(manifest_path, manifest);
+            }
+        }
+
+        // Удаление папки или появление `manifest.*` рядом с другими файлами
+        // меняет то, какие файлы считаются манифестами.
+        self.manifests
+            .write()
+            .unwrap()
+            .retain(|path, _| path.is_file() && self.is_manifest(path));
+
+        self.rebuild(&touched);
+    }
+
+    // При совпадении ID загружается манифест более приоритетного формата
+    // (см. `ManifestFormat`), а среди манифестов одного формата — идущий
+    // первым по пути; остальные получают ошибку в диагностике.
+    fn rebuild(&self, touched: &HashSet<String>) {
+        let manifests = self.manifests.read().unwrap();
+        let mut next: HashMap<String, Arc<dyn Installer>> = HashMap::new();
+        let mut owners: HashMap<String, &Path> = HashMap::new();
+        let mut files = Vec::new();
+
+        let mut ordered: Vec<_> = manifests.iter().collect();
+        ordered.sort_by_key(|(path, _)| (ManifestFormat::from_path(path), *path));
+        for (path, manifest) in ordered {
+            let mut diagnostics = manifest.diagnostics.clone();
+            let scripts: Vec<_> = manifest.scripts.iter().map(|(action, path)| (*action, path.as_path())).collect();
+            let mut loaded = None;
+
+            if let Some(installer) = &manifest.installer {
+                let id = installer.id();
+                diagnostics.extend(check_scripts(&scripts));
+                if self.builtin_ids.contains(id) {
+                    diagnostics.push(
+                        Diagnostic::error(format!("ID {} занят встроенным установщиком", id)).with_field("id"),
+                    );
+                } else if let Some(owner) = owners.get(id) {
+                    diagnostics.push(
+                        Diagnostic::error(format!("ID {} уже объявлен в {}", id, owner.display())).with_field("id"),
+                    );
+                } else {
+                    owners.insert(id.to_string(), path);
+                    next.insert(id.to_string(), Arc::clone(installer) as Arc<dyn Installer>);
+                    loaded = Some(installer);
+                }
+            }
+
+            files.push((path, manifest, loaded, diagnostics));
+        }
+
+        let mut report = CatalogDiagnostics {
+            checked: files.len(),
+            ..Default::default()
+        };
+        for (path, manifest, loaded, mut diagnostics) in files {
+            if let Some(installer) = loaded {
+                for dependency in installer.dependencies() {
+                    if !next.contains_key(&dependency) && !self.builtin_ids.contains(&dependency) {
+                        diagnostics.push(
+                            Diagnostic::warning(format!("Неизвестная зависимость {}", dependency))
+                                .with_field("dependencies"),
+                        );
+                    }
+                }
+                for (dependency, requirement) in installer.dependency_versions() {
+                    if let Some(target) = next.get(&dependency) {
+                        diagnostics.extend(check_requirement(&dependency, &requirement, target.version_scheme()));
+                    }
+                }
+            }
+            if diagnostics.is_empty() {
+                continue;
+            }
+            report.errors += diagnostics.iter().filter(|d| d.is_error()).count();
+            report.warnings += diagnostics.iter().filter(|d| !d.is_error()).count();
+            report.files.push(FileDiagnostics {
+                path: path.clone(),
+                id: manifest.id.clone(),
+                loaded: loaded.is_some(),
+                diagnostics,
+            });
+        }
+        report.files.sort_by(|a, b| a.path.cmp(&b.path));
+        *self.diagnostics.write().unwrap() = report;
+
+        let mut installers = self.installers.write().unwrap();
+        let mut change = CatalogChange {
+            added: next.keys().filter(|id| !installers.contains_key(*id)).cloned().collect(),
+            changed: next
+                .keys()
+                .filter(|id| installers.contains_key(*id) && touched.contains(*id))
+                .cloned()
+                .collect(),
+            removed: installers.keys().filter(|id| !next.contains_key(*id)).cloned().collect(),
+        };
+        *installers = next;
+
+        change.added.sort();
+        change.changed.sort();
+        change.removed.sort();
+        if !change.is_empty() {
+            let _ = self.reload_tx.send(change);
+        }
+    }
+
+    pub fn start_watcher(self: &Arc<Self>) {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let root = fs::canonicalize(&self.scripts_dir).unwrap_or_else(|_| self.scripts_dir.clone());
+        let watcher = create_watcher(&root, tx);
+        let loader = Arc::clone(self);
+
+        tokio::spawn(async move {
+            // Наблюдатель живёт, пока работает задача.
+            let _watcher = watcher;
+
+            while let Some(path) = rx.recv().await {
+                let mut paths = HashSet::from([path]);
+                let deadline = Instant::now() + DEBOUNCE_MAX;
+                loop {
+                    let quiet = (Instant::now() + DEBOUNCE_QUIET).min(deadline);
+                    match timeout_at(quiet, rx.recv()).await {
+                        Ok(Some(path)) => {
+                            paths.insert(path);
                         }
-                        let mut installers_guard = installers.write().unwrap();
-                        *installers_guard = loaded;
-                        let _ = reload_tx.send(());
+                        Ok(None) | Err(_) => break,
                     }
                 }
+
+                let paths = paths
+                    .into_iter()
+                    .map(|path| match path.strip_prefix(&root) {
+                        Ok(relative) => normalize(&loader.scripts_dir.join(relative)),
+                        Err(_) => normalize(&path),
+                    })
+                    .collect();
+                loader.apply_changes(paths).await;
             }
         });
     }
 }
 
+// inotify (или его аналог на других ОС), а если он недоступен — например,
+// исчерпан лимит наблюдений или каталог на сетевой ФС, — опрос раз в POLL_INTERVAL.
+fn create_watcher(root: &Path, tx: mpsc::UnboundedSender<PathBuf>) -> Option<Box<dyn Watcher + Send>> {
+    let handler = move |result: notify::Result<notify::Event>| {
+        let Ok(event) = result else {
+            return;
+        };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        for path in event.paths {
+            let _ = tx.send(path);
+        }
+    };
+
+    let native = RecommendedWatcher::new(handler.clone(), Config::default()).and_then(|mut watcher| {
+        watcher.watch(root, RecursiveMode::Recursive)?;
+        Ok(watcher)
+    });
+    let error = match native {
+        Ok(watcher) => return Some(Box::new(watcher)),
+        Err(e) => e,
+    };
+    eprintln!("Не удалось подписаться на изменения {}: {}; используется опрос", root.display(), error);
+
+    let config = Config::default().with_poll_interval(POLL_INTERVAL);
+    match PollWatcher::new(handler, config).and_then(|mut watcher| {
+        watcher.watch(root, RecursiveMode::Recursive)?;
+        Ok(watcher)
+    }) {
+        Ok(watcher) => Some(Box::new(watcher)),
+        Err(e) => {
+            eprintln!("Не удалось запустить наблюдение за {}: {}", root.display(), e);
+            None
+        }
+    }
+}
+
+// Приводит путь к единому виду без `.` и `..`, чтобы пути из событий
+// совпадали с путями скриптов из манифестов.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
@@ -1,17 +1,263 @@
+use crate::installer::ansi::StyledSpan;
+use crate::installer::redact::Redactor;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
-use chrono::Utc;
+use chrono::{DateTime, TimeDelta, Utc};
+use flate2::{write::GzEncoder, Compression};
+use uuid::Uuid;
+
+const FINAL_STATUSES: [&str; 4] = ["success", "failed", "timeout", "cancelled"];
+const LOG_EXTENSION: &str = "jsonl";
+const LEGACY_EXTENSION: &str = "json";
+const OVERFLOW_DIR: &str = "overflow";
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogEntry {
     pub timestamp: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
     pub action: String,
     pub status: String,
     pub message: String,
     pub output: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution: Option<ExecutionRecord>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overflow: Option<String>,
+}
+
+impl LogEntry {
+    pub fn new(action: &str, status: &str, message: &str, output: &str) -> Self {
+        Self {
+            timestamp: Utc::now().to_rfc3339(),
+            run_id: None,
+            action: action.to_string(),
+            status: status.to_string(),
+            message: message.to_string(),
+            output: output.to_string(),
+            progress: None,
+            execution: None,
+            overflow: None,
+        }
+    }
+
+    pub fn with_run(mut self, run_id: &str) -> Self {
+        self.run_id = Some(run_id.to_string());
+        self
+    }
+
+    pub fn with_progress(mut self, progress: u8) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub fn with_execution(mut self, execution: Option<ExecutionRecord>) -> Self {
+        self.execution = execution;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionRecord {
+    pub script: Option<String>,
+    pub manifest_revision: Option<String>,
+    pub exit_code: Option<i32>,
+    pub signal: Option<i32>,
+    pub started_at: String,
+    pub finished_at: String,
+    pub duration_ms: u64,
+    pub stdout: String,
+    pub stderr: String,
+    // Исходный вывод с управляющими последовательностями — только если
+    // он отличается от текста.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdout_raw: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr_raw: Option<String>,
+    // Цветное оформление вывода — только если скрипт его использовал.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdout_spans: Option<Vec<StyledSpan>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr_spans: Option<Vec<StyledSpan>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub run_id: String,
+    pub installer_id: String,
+    pub action: String,
+    pub status: String,
+    pub message: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub duration_ms: Option<u64>,
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunDetails {
+    #[serde(flatten)]
+    pub summary: RunSummary,
+    pub events: Vec<LogEntry>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+// Параметры выборки логов. `action` и `status` принимают несколько значений
+// через запятую, `q` ищет подстроку без учёта регистра в сообщении и выводе.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LogQuery {
+    pub installer: Option<String>,
+    pub action: Option<String>,
+    pub status: Option<String>,
+    pub run_id: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub q: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub order: LogOrder,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogRecord {
+    pub installer_id: String,
+    #[serde(flatten)]
+    pub entry: LogEntry,
+}
+
+impl From<(LogKey, &LogEntry)> for LogRecord {
+    fn from((key, entry): (LogKey, &LogEntry)) -> Self {
+        LogRecord {
+            installer_id: key.installer_id,
+            entry: entry.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogPage {
+    pub entries: Vec<LogRecord>,
+    pub next_cursor: Option<String>,
+}
+
+// Позиция записи в общей ленте: время, установщик и порядковый номер
+// среди записей этого установщика с тем же временем.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct LogKey {
+    time: DateTime<Utc>,
+    installer_id: String,
+    ordinal: usize,
+}
+
+impl LogKey {
+    fn to_cursor(&self) -> String {
+        let raw = format!("{}\n{}\n{}", self.time.to_rfc3339(), self.ordinal, self.installer_id);
+        raw.bytes().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn from_cursor(cursor: &str) -> Option<Self> {
+        if !cursor.len().is_multiple_of(2) {
+            return None;
+        }
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(cursor.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        let raw = String::from_utf8(bytes).ok()?;
+        let mut parts = raw.splitn(3, '\n');
+        let time = DateTime::parse_from_rfc3339(parts.next()?).ok()?.with_timezone(&Utc);
+        let ordinal = parts.next()?.parse().ok()?;
+        let installer_id = parts.next()?.to_string();
+        Some(Self {
+            time,
+            installer_id,
+            ordinal,
+        })
+    }
+}
+
+struct LogFilter {
+    actions: Option<Vec<String>>,
+    statuses: Option<Vec<String>>,
+    run_id: Option<String>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    text: Option<String>,
+}
+
+impl LogFilter {
+    fn new(query: &LogQuery) -> Result<Self, String> {
+        let list = |value: &Option<String>| {
+            value.as_ref().map(|v| {
+                v.split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>()
+            })
+        };
+        let time = |name: &str, value: &Option<String>| -> Result<Option<DateTime<Utc>>, String> {
+            value
+                .as_deref()
+                .map(|v| {
+                    DateTime::parse_from_rfc3339(v)
+                        .map(|t| t.with_timezone(&Utc))
+                        .map_err(|_| format!("Параметр {} должен быть датой в формате RFC 3339", name))
+                })
+                .transpose()
+        };
+
+        Ok(Self {
+            actions: list(&query.action),
+            statuses: list(&query.status),
+            run_id: query.run_id.clone(),
+            since: time("since", &query.since)?,
+            until: time("until", &query.until)?,
+            text: query.q.as_ref().map(|q| q.to_lowercase()).filter(|q| !q.is_empty()),
+        })
+    }
+
+    fn matches(&self, entry: &LogEntry, time: DateTime<Utc>) -> bool {
+        if self.actions.as_ref().is_some_and(|a| !a.contains(&entry.action)) {
+            return false;
+        }
+        if self.statuses.as_ref().is_some_and(|s| !s.contains(&entry.status)) {
+            return false;
+        }
+        if self.run_id.as_ref().is_some_and(|r| entry.run_id.as_ref() != Some(r)) {
+            return false;
+        }
+        if self.since.is_some_and(|since| time < since) || self.until.is_some_and(|until| time > until) {
+            return false;
+        }
+        let Some(text) = &self.text else {
+            return true;
+        };
+        let found = |field: &str| field.to_lowercase().contains(text);
+        found(&entry.message)
+            || found(&entry.output)
+            || entry
+                .execution
+                .as_ref()
+                .is_some_and(|e| found(&e.stdout) || found(&e.stderr))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,73 +266,555 @@ pub struct InstallerLogs {
     pub entries: Vec<LogEntry>,
 }
 
+// Лимиты хранения истории одного установщика. `None` — без ограничения.
+#[derive(Debug, Clone)]
+pub struct LogRetention {
+    pub max_entries: Option<usize>,
+    pub max_age_days: Option<i64>,
+    pub max_bytes: Option<u64>,
+    pub max_output_bytes: usize,
+}
+
+impl Default for LogRetention {
+    fn default() -> Self {
+        Self {
+            max_entries: Some(1000),
+            max_age_days: Some(30),
+            max_bytes: Some(10 * 1024 * 1024),
+            max_output_bytes: 64 * 1024,
+        }
+    }
+}
+
+impl LogRetention {
+    // KITSUNE_LOG_MAX_ENTRIES, KITSUNE_LOG_MAX_AGE_DAYS, KITSUNE_LOG_MAX_BYTES,
+    // KITSUNE_LOG_MAX_OUTPUT_BYTES; значение 0 снимает ограничение.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            max_entries: env_limit("KITSUNE_LOG_MAX_ENTRIES").unwrap_or(defaults.max_entries),
+            max_age_days: env_limit("KITSUNE_LOG_MAX_AGE_DAYS").unwrap_or(defaults.max_age_days),
+            max_bytes: env_limit("KITSUNE_LOG_MAX_BYTES").unwrap_or(defaults.max_bytes),
+            max_output_bytes: env_limit("KITSUNE_LOG_MAX_OUTPUT_BYTES")
+                .map(|limit| limit.unwrap_or(usize::MAX))
+                .unwrap_or(defaults.max_output_bytes),
+        }
+    }
+
+    fn exceeded(&self, log: &InstallerLog) -> bool {
+        self.max_entries.is_some_and(|max| log.entries.len() > max)
+            || self.max_bytes.is_some_and(|max| log.bytes > max)
+            || self.age_cutoff(100).is_some_and(|cutoff| {
+                log.entries
+                    .first()
+                    .and_then(entry_time)
+                    .is_some_and(|time| time < cutoff)
+            })
+    }
+
+    // Записи старше `percent` процентов лимита по возрасту. Лимит, который
+    // не укладывается в диапазон дат, равносилен его отсутствию.
+    fn age_cutoff(&self, percent: i64) -> Option<DateTime<Utc>> {
+        let hours = self.max_age_days?.checked_mul(24 * percent)? / 100;
+        Utc::now().checked_sub_signed(TimeDelta::try_hours(hours)?)
+    }
+}
+
+fn env_limit<T: FromStr + PartialEq + Default>(name: &str) -> Option<Option<T>> {
+    let value = std::env::var(name).ok()?.trim().parse::<T>().ok()?;
+    Some((value != T::default()).then_some(value))
+}
+
+fn entry_time(entry: &LogEntry) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&entry.timestamp)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+#[derive(Default)]
+struct InstallerLog {
+    entries: Vec<LogEntry>,
+    bytes: u64,
+}
+
+#[derive(Serialize)]
+struct OverflowRecord<'a> {
+    message: &'a str,
+    output: &'a str,
+    stdout: Option<&'a str>,
+    stderr: Option<&'a str>,
+    stdout_raw: Option<&'a str>,
+    stderr_raw: Option<&'a str>,
+}
+
+// Логи хранятся по файлу на установщик в формате JSON Lines и дописываются
+// построчно; файл переписывается целиком только при применении лимитов.
+// История установщика читается с диска при первом обращении.
 pub struct Logger {
     logs_dir: PathBuf,
-    logs: Arc<RwLock<HashMap<String, Vec<LogEntry>>>>,
+    retention: LogRetention,
+    logs: Arc<RwLock<HashMap<String, InstallerLog>>>,
+    // Запуск → установщик, для загруженных историй.
+    runs: Arc<RwLock<HashMap<String, String>>>,
+    all_loaded: AtomicBool,
 }
 
 impl Logger {
-    pub fn new(logs_dir: PathBuf) -> Self {
+    pub fn new(logs_dir: PathBuf, retention: LogRetention) -> Self {
         if !logs_dir.exists() {
             let _ = fs::create_dir_all(&logs_dir);
         }
 
-        let mut logs = HashMap::new();
-        
-        if let Ok(entries) = fs::read_dir(&logs_dir) {
-            for entry in entries.flatten() {
-                if let Some(id) = entry.file_name().to_str() {
-                    if id.ends_with(".json") {
-                        let id = id.trim_end_matches(".json");
-                        if let Ok(content) = fs::read_to_string(entry.path()) {
-                            if let Ok(logs_data) = serde_json::from_str::<InstallerLogs>(&content) {
-                                logs.insert(id.to_string(), logs_data.entries);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
         Self {
             logs_dir,
-            logs: Arc::new(RwLock::new(logs)),
+            retention,
+            logs: Arc::new(RwLock::new(HashMap::new())),
+            runs: Arc::new(RwLock::new(HashMap::new())),
+            all_loaded: AtomicBool::new(false),
         }
     }
 
     pub fn log(&self, installer_id: &str, action: &str, status: &str, message: &str, output: &str) {
-        let entry = LogEntry {
-            timestamp: Utc::now().to_rfc3339(),
-            action: action.to_string(),
-            status: status.to_string(),
-            message: message.to_string(),
-            output: output.to_string(),
+        self.append(installer_id, LogEntry::new(action, status, message, output));
+    }
+
+    pub fn append(&self, installer_id: &str, mut entry: LogEntry) {
+        let Some(path) = self.log_path(installer_id, LOG_EXTENSION) else {
+            return;
+        };
+        // Вывод скриптов уже замаскирован при запуске; здесь общие шаблоны
+        // применяются ко всему, что попадает в журнал другими путями.
+        let redactor = Redactor::default();
+        redactor.redact_in_place(&mut entry.message);
+        redactor.redact_in_place(&mut entry.output);
+        if let Some(execution) = &mut entry.execution {
+            redactor.redact_in_place(&mut execution.stdout);
+            redactor.redact_in_place(&mut execution.stderr);
+        }
+        self.spill_overflow(installer_id, &mut entry);
+        let Ok(line) = serde_json::to_string(&entry) else {
+            return;
         };
 
         let mut logs = self.logs.write().unwrap();
-        let entries = logs.entry(installer_id.to_string()).or_insert_with(Vec::new);
-        entries.push(entry.clone());
+        let log = logs
+            .entry(installer_id.to_string())
+            .or_insert_with(|| self.read_log(installer_id));
 
-        let logs_data = InstallerLogs {
-            installer_id: installer_id.to_string(),
-            entries: entries.clone(),
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| writeln!(file, "{}", line));
+        if written.is_err() {
+            return;
+        }
+        log.bytes += line.len() as u64 + 1;
+        self.index_runs(installer_id, std::slice::from_ref(&entry));
+        log.entries.push(entry);
+
+        if self.retention.exceeded(log) {
+            self.compact(installer_id, log);
+        }
+    }
+
+    pub fn get_runs(&self, installer_id: &str) -> Vec<RunSummary> {
+        self.with_log(installer_id, |log| {
+            let mut runs: Vec<(&str, Vec<&LogEntry>)> = Vec::new();
+            for entry in &log.entries {
+                let Some(run_id) = entry.run_id.as_deref() else {
+                    continue;
+                };
+                match runs.iter_mut().find(|(id, _)| *id == run_id) {
+                    Some((_, run)) => run.push(entry),
+                    None => runs.push((run_id, vec![entry])),
+                }
+            }
+
+            runs.iter()
+                .rev()
+                .map(|(run_id, run)| summarize(run_id, installer_id, run))
+                .collect()
+        })
+        .unwrap_or_default()
+    }
+
+    pub fn get_run(&self, run_id: &str) -> Option<RunDetails> {
+        // Полный обход логов нужен только пока не все истории загружены.
+        let installer_id = match self.run_installer(run_id) {
+            Some(id) => id,
+            None => {
+                self.load_all();
+                self.run_installer(run_id)?
+            }
         };
+        self.with_log(&installer_id, |log| {
+            let events: Vec<LogEntry> = log
+                .entries
+                .iter()
+                .filter(|e| e.run_id.as_deref() == Some(run_id))
+                .cloned()
+                .collect();
+            if events.is_empty() {
+                return None;
+            }
+            let summary = summarize(run_id, &installer_id, &events.iter().collect::<Vec<_>>());
+            Some(RunDetails { summary, events })
+        })
+        .flatten()
+    }
 
-        let log_file = self.logs_dir.join(format!("{}.json", installer_id));
-        if let Ok(json) = serde_json::to_string_pretty(&logs_data) {
-            let _ = fs::write(&log_file, json);
+    fn run_installer(&self, run_id: &str) -> Option<String> {
+        self.runs.read().unwrap().get(run_id).cloned()
+    }
+
+    fn index_runs(&self, installer_id: &str, entries: &[LogEntry]) {
+        let mut runs = self.runs.write().unwrap();
+        for run_id in entries.iter().filter_map(|e| e.run_id.as_ref()) {
+            if !runs.contains_key(run_id) {
+                runs.insert(run_id.clone(), installer_id.to_string());
+            }
         }
     }
 
-    pub fn get_logs(&self, installer_id: &str) -> Vec<LogEntry> {
-        let logs = self.logs.read().unwrap();
-        logs.get(installer_id).cloned().unwrap_or_default()
+    pub fn query(&self, query: &LogQuery) -> Result<LogPage, String> {
+        let cursor = query
+            .cursor
+            .as_deref()
+            .map(|c| LogKey::from_cursor(c).ok_or_else(|| "Некорректный курсор".to_string()))
+            .transpose()?;
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+        self.select(query, |mut matched| {
+            if query.order == LogOrder::Desc {
+                matched.reverse();
+            }
+            if let Some(cursor) = &cursor {
+                matched.retain(|(key, _)| match query.order {
+                    LogOrder::Asc => key > cursor,
+                    LogOrder::Desc => key < cursor,
+                });
+            }
+
+            let next_cursor = (matched.len() > limit).then(|| matched[limit - 1].0.to_cursor());
+            matched.truncate(limit);
+            let entries = matched.into_iter().map(LogRecord::from).collect();
+
+            LogPage { entries, next_cursor }
+        })
     }
 
-    #[allow(dead_code)]
-    pub fn get_all_logs(&self) -> HashMap<String, Vec<LogEntry>> {
+    // Выгрузка отдаёт все подходящие записи в хронологическом порядке;
+    // `cursor`, `limit` и `order` не учитываются.
+    pub fn export(&self, query: &LogQuery) -> Result<Vec<LogRecord>, String> {
+        self.select(query, |matched| matched.into_iter().map(LogRecord::from).collect())
+    }
+
+    // Подходящие записи передаются по ссылке, отсортированными по ключу, —
+    // копируется только то, что вернёт `f`.
+    fn select<R>(&self, query: &LogQuery, f: impl FnOnce(Vec<(LogKey, &LogEntry)>) -> R) -> Result<R, String> {
+        let filter = LogFilter::new(query)?;
+
+        match &query.installer {
+            Some(installer_id) => {
+                self.with_log(installer_id, |_| ());
+            }
+            None => self.load_all(),
+        }
+
         let logs = self.logs.read().unwrap();
-        logs.clone()
+        let mut matched = Vec::new();
+        for (installer_id, log) in logs.iter() {
+            if query.installer.as_ref().is_some_and(|id| id != installer_id) {
+                continue;
+            }
+            let mut previous: Option<DateTime<Utc>> = None;
+            let mut ordinal = 0;
+            for entry in &log.entries {
+                let Some(time) = entry_time(entry) else {
+                    continue;
+                };
+                ordinal = if previous == Some(time) { ordinal + 1 } else { 0 };
+                previous = Some(time);
+                if !filter.matches(entry, time) {
+                    continue;
+                }
+                let key = LogKey {
+                    time,
+                    installer_id: installer_id.clone(),
+                    ordinal,
+                };
+                matched.push((key, entry));
+            }
+        }
+
+        matched.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(f(matched))
+    }
+
+    fn with_log<R>(&self, installer_id: &str, f: impl FnOnce(&InstallerLog) -> R) -> Option<R> {
+        {
+            let logs = self.logs.read().unwrap();
+            if let Some(log) = logs.get(installer_id) {
+                return Some(f(log));
+            }
+        }
+
+        // Не кэшируем установщики без истории на диске, чтобы запросы
+        // с произвольными ID не раздували память.
+        if !self.has_log_on_disk(installer_id) {
+            return None;
+        }
+        let mut logs = self.logs.write().unwrap();
+        let log = logs
+            .entry(installer_id.to_string())
+            .or_insert_with(|| self.read_log(installer_id));
+        Some(f(log))
+    }
+
+    fn load_all(&self) {
+        if self.all_loaded.load(Ordering::Acquire) {
+            return;
+        }
+        let Ok(entries) = fs::read_dir(&self.logs_dir) else {
+            return;
+        };
+        let ids: Vec<String> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                let extension = path.extension()?.to_str()?;
+                if extension != LOG_EXTENSION && extension != LEGACY_EXTENSION {
+                    return None;
+                }
+                Some(path.file_stem()?.to_str()?.to_string())
+            })
+            .collect();
+
+        let mut logs = self.logs.write().unwrap();
+        for id in ids {
+            logs.entry(id.clone()).or_insert_with(|| self.read_log(&id));
+        }
+        self.all_loaded.store(true, Ordering::Release);
     }
+
+    fn log_path(&self, installer_id: &str, extension: &str) -> Option<PathBuf> {
+        let safe = !installer_id.is_empty()
+            && !installer_id.contains(['/', '\\'])
+            && installer_id != "."
+            && installer_id != "..";
+        safe.then(|| self.logs_dir.join(format!("{}.{}", installer_id, extension)))
+    }
+
+    fn has_log_on_disk(&self, installer_id: &str) -> bool {
+        [LOG_EXTENSION, LEGACY_EXTENSION]
+            .iter()
+            .filter_map(|extension| self.log_path(installer_id, extension))
+            .any(|path| path.exists())
+    }
+
+    fn read_log(&self, installer_id: &str) -> InstallerLog {
+        self.migrate_legacy(installer_id);
+
+        let mut log = InstallerLog::default();
+        let Some(path) = self.log_path(installer_id, LOG_EXTENSION) else {
+            return log;
+        };
+        let Ok(file) = fs::File::open(&path) else {
+            return log;
+        };
+
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            if let Ok(entry) = serde_json::from_str::<LogEntry>(&line) {
+                log.bytes += line.len() as u64 + 1;
+                log.entries.push(entry);
+            }
+        }
+
+        if self.retention.exceeded(&log) {
+            self.compact(installer_id, &mut log);
+        }
+        self.index_runs(installer_id, &log.entries);
+        log
+    }
+
+    // Переносит историю из старого формата `<id>.json` (весь массив в одном документе).
+    fn migrate_legacy(&self, installer_id: &str) {
+        let (Some(legacy), Some(path)) = (
+            self.log_path(installer_id, LEGACY_EXTENSION),
+            self.log_path(installer_id, LOG_EXTENSION),
+        ) else {
+            return;
+        };
+        if !legacy.exists() || path.exists() {
+            return;
+        }
+        let Ok(content) = fs::read_to_string(&legacy) else {
+            return;
+        };
+        let Ok(data) = serde_json::from_str::<InstallerLogs>(&content) else {
+            return;
+        };
+        if self.write_entries(installer_id, &data.entries).is_ok() {
+            let _ = fs::remove_file(&legacy);
+        }
+    }
+
+    fn write_entries(&self, installer_id: &str, entries: &[LogEntry]) -> std::io::Result<u64> {
+        let path = self
+            .log_path(installer_id, LOG_EXTENSION)
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+        let temp = path.with_extension(format!("{}.tmp", LOG_EXTENSION));
+
+        let mut bytes = 0;
+        let mut content = String::new();
+        for entry in entries {
+            if let Ok(line) = serde_json::to_string(entry) {
+                bytes += line.len() as u64 + 1;
+                content.push_str(&line);
+                content.push('\n');
+            }
+        }
+        fs::write(&temp, content)?;
+        fs::rename(&temp, &path)?;
+        Ok(bytes)
+    }
+
+    // Удаляет самые старые записи до 90% каждого лимита, чтобы не переписывать
+    // файл на каждой следующей записи.
+    fn compact(&self, installer_id: &str, log: &mut InstallerLog) {
+        let retention = &self.retention;
+        let sizes: Vec<u64> = log
+            .entries
+            .iter()
+            .map(|entry| serde_json::to_string(entry).map(|l| l.len() as u64 + 1).unwrap_or(0))
+            .collect();
+
+        let mut drop = 0;
+        if let Some(cutoff) = retention.age_cutoff(90) {
+            drop = log
+                .entries
+                .iter()
+                .take_while(|entry| entry_time(entry).is_some_and(|time| time < cutoff))
+                .count();
+        }
+        if let Some(max) = retention.max_entries {
+            let target = max - max / 10;
+            drop = drop.max(log.entries.len().saturating_sub(target));
+        }
+        if let Some(max) = retention.max_bytes {
+            let target = max - max / 10;
+            let mut total: u64 = sizes[drop..].iter().sum();
+            while total > target && drop < log.entries.len() {
+                total -= sizes[drop];
+                drop += 1;
+            }
+        }
+        if drop == 0 {
+            return;
+        }
+
+        let removed: Vec<LogEntry> = log.entries.drain(..drop).collect();
+        match self.write_entries(installer_id, &log.entries) {
+            Ok(bytes) => log.bytes = bytes,
+            Err(_) => log.bytes = sizes[drop..].iter().sum(),
+        }
+        for entry in removed {
+            if let Some(overflow) = entry.overflow {
+                let _ = fs::remove_file(self.logs_dir.join(OVERFLOW_DIR).join(overflow));
+            }
+        }
+    }
+
+    // Слишком большой вывод обрезается до хвоста, а полный текст сохраняется
+    // рядом в сжатом файле, имя которого записывается в `overflow`.
+    fn spill_overflow(&self, installer_id: &str, entry: &mut LogEntry) {
+        let limit = self.retention.max_output_bytes;
+        let too_long = entry.message.len() > limit
+            || entry.output.len() > limit
+            || entry.execution.as_ref().is_some_and(|e| {
+                [&e.stdout, &e.stderr].iter().any(|text| text.len() > limit)
+                    || [&e.stdout_raw, &e.stderr_raw].iter().any(|raw| raw.as_ref().is_some_and(|r| r.len() > limit))
+            });
+        if !too_long {
+            return;
+        }
+
+        let name = format!("{}-{}.json.gz", installer_id, Uuid::new_v4());
+        let record = OverflowRecord {
+            message: &entry.message,
+            output: &entry.output,
+            stdout: entry.execution.as_ref().map(|e| e.stdout.as_str()),
+            stderr: entry.execution.as_ref().map(|e| e.stderr.as_str()),
+            stdout_raw: entry.execution.as_ref().and_then(|e| e.stdout_raw.as_deref()),
+            stderr_raw: entry.execution.as_ref().and_then(|e| e.stderr_raw.as_deref()),
+        };
+        let dir = self.logs_dir.join(OVERFLOW_DIR);
+        let saved = fs::create_dir_all(&dir).and_then(|_| {
+            let file = fs::File::create(dir.join(&name))?;
+            let mut encoder = GzEncoder::new(file, Compression::default());
+            serde_json::to_writer(&mut encoder, &record)?;
+            encoder.finish().map(|_| ())
+        });
+
+        truncate_to_tail(&mut entry.message, limit);
+        truncate_to_tail(&mut entry.output, limit);
+        if let Some(execution) = entry.execution.as_mut() {
+            // Спаны обрезанного текста больше ему не соответствуют; полный
+            // исходный вывод остаётся в файле переполнения.
+            if execution.stdout.len() > limit {
+                execution.stdout_spans = None;
+            }
+            if execution.stderr.len() > limit {
+                execution.stderr_spans = None;
+            }
+            truncate_to_tail(&mut execution.stdout, limit);
+            truncate_to_tail(&mut execution.stderr, limit);
+            if let Some(raw) = execution.stdout_raw.as_mut() {
+                truncate_to_tail(raw, limit);
+            }
+            if let Some(raw) = execution.stderr_raw.as_mut() {
+                truncate_to_tail(raw, limit);
+            }
+        }
+        if saved.is_ok() {
+            entry.overflow = Some(name);
+        }
+    }
+}
+
+fn truncate_to_tail(text: &mut String, limit: usize) {
+    if text.len() <= limit {
+        return;
+    }
+    let mut start = text.len() - limit;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    *text = format!("…[обрезано {} байт]\n{}", start, &text[start..]);
 }
 
+fn summarize(run_id: &str, installer_id: &str, entries: &[&LogEntry]) -> RunSummary {
+    let first = entries[0];
+    let last = entries[entries.len() - 1];
+    let finished = FINAL_STATUSES.contains(&last.status.as_str());
+    let finished_at = finished.then(|| last.timestamp.clone());
+
+    let duration_ms = finished_at.as_deref().and_then(|finished_at| {
+        let start = DateTime::parse_from_rfc3339(&first.timestamp).ok()?;
+        let end = DateTime::parse_from_rfc3339(finished_at).ok()?;
+        u64::try_from((end - start).num_milliseconds()).ok()
+    });
+
+    RunSummary {
+        run_id: run_id.to_string(),
+        installer_id: installer_id.to_string(),
+        action: first.action.clone(),
+        status: if finished { last.status.clone() } else { "running".to_string() },
+        message: last.message.clone(),
+        started_at: first.timestamp.clone(),
+        finished_at,
+        duration_ms,
+        entries: entries.len(),
+    }
+}
@@ -1,12 +1,28 @@
+pub mod ansi;
+pub mod batch;
+pub mod dependency;
+pub mod error;
+pub mod export;
 pub mod module;
+pub mod schema;
 pub mod script;
+pub mod validation;
 pub mod loader;
 pub mod logger;
+pub mod operation;
+pub mod redact;
+pub mod version;
 
-pub use logger::{LogEntry, Logger};
+pub use error::InstallerError;
+pub use logger::{ExecutionRecord, LogEntry, LogPage, LogQuery, LogRetention, Logger, RunDetails, RunSummary};
+pub use operation::{Operation, OperationManager};
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use version::{compare_versions, VersionScheme};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InstallerInfo {
@@ -18,13 +34,34 @@ pub struct InstallerInfo {
     pub current_version: Option<String>,
     pub latest_version: Option<String>,
     pub can_update: bool,
+    pub ahead_of_latest: bool,
     pub dependencies: Vec<String>,
+    pub dependency_versions: BTreeMap<String, String>,
+    pub install_path: Option<String>,
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+    pub messages: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InstallerDetails {
+    pub install_path: Option<String>,
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+    pub messages: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InstallationState {
+    pub installed: bool,
+    pub current_version: Option<String>,
+    pub details: InstallerDetails,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InstallResult {
     pub success: bool,
     pub message: String,
+    #[serde(default)]
+    pub error: Option<InstallerError>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,11 +78,48 @@ pub enum InstallStatus {
     Installing,
     Completed,
     Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallAction {
+    Install,
+    Update,
+    Uninstall,
+}
+
+impl InstallAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InstallAction::Install => "install",
+            InstallAction::Update => "update",
+            InstallAction::Uninstall => "uninstall",
+        }
+    }
+
+    pub fn started_message(&self) -> &'static str {
+        match self {
+            InstallAction::Install => "Начало установки",
+            InstallAction::Update => "Начало обновления",
+            InstallAction::Uninstall => "Начало удаления",
+        }
+    }
+
+    pub fn failed_message(&self) -> &'static str {
+        match self {
+            InstallAction::Install => "Ошибка установки",
+            InstallAction::Update => "Ошибка обновления",
+            InstallAction::Uninstall => "Ошибка удаления",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BatchInstallRequest {
     pub ids: Vec<String>,
+    #[serde(default)]
+    pub concurrency: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,25 +138,65 @@ pub trait Installer: Send + Sync {
     fn category(&self) -> &str;
     fn dependencies(&self) -> Vec<String>;
 
+    fn dependency_versions(&self) -> BTreeMap<String, String> {
+        BTreeMap::new()
+    }
+
+    fn version_scheme(&self) -> VersionScheme {
+        VersionScheme::default()
+    }
+
     async fn check_installed(&self) -> bool;
     async fn get_current_version(&self) -> Option<String>;
     async fn get_latest_version(&self) -> Option<String>;
-    async fn install(&self) -> Result<InstallResult, String>;
-    async fn update(&self) -> Result<InstallResult, String>;
-    async fn uninstall(&self) -> Result<InstallResult, String>;
+    async fn install(&self, operation: &Operation) -> Result<InstallResult, InstallerError>;
+    async fn update(&self, operation: &Operation) -> Result<InstallResult, InstallerError>;
+    async fn uninstall(&self, operation: &Operation) -> Result<InstallResult, InstallerError>;
 
-    async fn get_info(&self) -> InstallerInfo {
+    async fn cleanup(&self) -> Option<Result<InstallResult, InstallerError>> {
+        None
+    }
+
+    async fn run_action(&self, action: InstallAction, operation: &Operation) -> Result<InstallResult, InstallerError> {
+        match action {
+            InstallAction::Install => self.install(operation).await,
+            InstallAction::Update => self.update(operation).await,
+            InstallAction::Uninstall => self.uninstall(operation).await,
+        }
+    }
+
+    async fn inspect(&self) -> InstallationState {
         let installed = self.check_installed().await;
         let current_version = if installed {
             self.get_current_version().await
         } else {
             None
         };
+        InstallationState {
+            installed,
+            current_version,
+            details: InstallerDetails::default(),
+        }
+    }
+
+    async fn get_info(&self) -> InstallerInfo {
+        let InstallationState {
+            installed,
+            current_version,
+            details,
+        } = self.inspect().await;
         let latest_version = self.get_latest_version().await;
-        let can_update = installed
-            && current_version.is_some()
-            && latest_version.is_some()
-            && current_version != latest_version;
+        // Версии, не разобранные по выбранной схеме, сравниваются как строки,
+        // как в схеме `exact`: любое отличие считается обновлением.
+        let ordering = match (&current_version, &latest_version) {
+            (Some(current), Some(latest)) => compare_versions(current, latest, self.version_scheme()).or_else(|| {
+                warn_incomparable(self.id(), current, latest, self.version_scheme());
+                compare_versions(current, latest, VersionScheme::Exact)
+            }),
+            _ => None,
+        };
+        let can_update = installed && ordering == Some(Ordering::Less);
+        let ahead_of_latest = installed && ordering == Some(Ordering::Greater);
 
         InstallerInfo {
             id: self.id().to_string(),
@@ -93,7 +207,25 @@ pub trait Installer: Send + Sync {
             current_version,
             latest_version,
             can_update,
+            ahead_of_latest,
             dependencies: self.dependencies(),
+            dependency_versions: self.dependency_versions(),
+            install_path: details.install_path,
+            metadata: details.metadata,
+            messages: details.messages,
         }
     }
 }
+
+// Список установщиков запрашивается часто, поэтому о несравнимых версиях
+// сообщается один раз для каждой пары версий установщика.
+fn warn_incomparable(id: &str, current: &str, latest: &str, scheme: VersionScheme) {
+    static REPORTED: OnceLock<Mutex<HashSet<(String, String, String)>>> = OnceLock::new();
+    let key = (id.to_string(), current.to_string(), latest.to_string());
+    if REPORTED.get_or_init(Default::default).lock().unwrap().insert(key) {
+        eprintln!(
+            "Не удалось сравнить версии {} и {} установщика {} по схеме {:?}",
+            current, latest, id, scheme
+        );
+    }
+}
@@ -0,0 +1,412 @@
+use crate::installer::ansi::StyledSpan;
+use crate::installer::{ExecutionRecord, InstallAction, InstallProgress, InstallResult, InstallStatus};
+use chrono::Utc;
+use futures::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::{watch, Mutex as AsyncMutex, OwnedMutexGuard};
+use uuid::Uuid;
+
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+const MAX_FINISHED_OPERATIONS: usize = 200;
+const MAX_REPLAY_OUTPUT_LINES: usize = 1000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OperationEvent {
+    Output {
+        stream: OutputStream,
+        line: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        spans: Option<Vec<StyledSpan>>,
+    },
+    Progress { progress: InstallProgress },
+    Exit { success: bool, message: String },
+}
+
+impl OperationEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OperationEvent::Output { .. } => "output",
+            OperationEvent::Progress { .. } => "progress",
+            OperationEvent::Exit { .. } => "exit",
+        }
+    }
+
+    pub fn is_exit(&self) -> bool {
+        matches!(self, OperationEvent::Exit { .. })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationInfo {
+    pub id: String,
+    pub installer_id: String,
+    pub action: InstallAction,
+    pub progress: InstallProgress,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub result: Option<InstallResult>,
+}
+
+struct OperationState {
+    progress: InstallProgress,
+    started_at: Option<String>,
+    finished_at: Option<String>,
+    result: Option<InstallResult>,
+}
+
+// Буфер для повторной отправки событий новым подписчикам: прогресс и
+// завершение хранятся все, вывод — только последние строки. События
+// нумеруются, чтобы при отдаче сохранить исходный порядок.
+#[derive(Default)]
+struct ReplayBuffer {
+    next: u64,
+    output: VecDeque<(u64, OperationEvent)>,
+    other: Vec<(u64, OperationEvent)>,
+}
+
+impl ReplayBuffer {
+    fn push(&mut self, event: OperationEvent) {
+        let entry = (self.next, event);
+        self.next += 1;
+        match entry.1 {
+            OperationEvent::Output { .. } => {
+                if self.output.len() == MAX_REPLAY_OUTPUT_LINES {
+                    self.output.pop_front();
+                }
+                self.output.push_back(entry);
+            }
+            _ => self.other.push(entry),
+        }
+    }
+
+    fn events(&self) -> Vec<OperationEvent> {
+        let mut events: Vec<_> = self.output.iter().chain(&self.other).collect();
+        events.sort_by_key(|(seq, _)| *seq);
+        events.into_iter().map(|(_, event)| event.clone()).collect()
+    }
+}
+
+pub struct Operation {
+    id: String,
+    installer_id: String,
+    action: InstallAction,
+    created_at: String,
+    events: Mutex<ReplayBuffer>,
+    state: Mutex<OperationState>,
+    tx: broadcast::Sender<OperationEvent>,
+    cancel_tx: watch::Sender<bool>,
+    executions: Mutex<Vec<ExecutionRecord>>,
+}
+
+impl Operation {
+    fn new(installer_id: &str, action: InstallAction) -> Self {
+        let (tx, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        let (cancel_tx, _) = watch::channel(false);
+        Self {
+            id: Uuid::new_v4().to_string(),
+            installer_id: installer_id.to_string(),
+            action,
+            created_at: Utc::now().to_rfc3339(),
+            events: Mutex::new(ReplayBuffer::default()),
+            state: Mutex::new(OperationState {
+                progress: InstallProgress {
+                    id: installer_id.to_string(),
+                    status: InstallStatus::Pending,
+                    progress: 0,
+                    message: "Ожидание...".to_string(),
+                },
+                started_at: None,
+                finished_at: None,
+                result: None,
+            }),
+            tx,
+            cancel_tx,
+            executions: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn installer_id(&self) -> &str {
+        &self.installer_id
+    }
+
+    pub fn action(&self) -> InstallAction {
+        self.action
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.lock().unwrap().finished_at.is_some()
+    }
+
+    pub fn info(&self) -> OperationInfo {
+        let state = self.state.lock().unwrap();
+        OperationInfo {
+            id: self.id.clone(),
+            installer_id: self.installer_id.clone(),
+            action: self.action,
+            progress: state.progress.clone(),
+            created_at: self.created_at.clone(),
+            started_at: state.started_at.clone(),
+            finished_at: state.finished_at.clone(),
+            result: state.result.clone(),
+        }
+    }
+
+    pub fn start(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.finished_at.is_some() {
+            return;
+        }
+        state.started_at = Some(Utc::now().to_rfc3339());
+        state.progress.status = InstallStatus::Installing;
+        state.progress.message = self.action.started_message().to_string();
+        self.publish(OperationEvent::Progress {
+            progress: state.progress.clone(),
+        });
+    }
+
+    pub fn output(&self, stream: OutputStream, line: &str, spans: Option<Vec<StyledSpan>>) {
+        self.publish(OperationEvent::Output {
+            stream,
+            line: line.to_string(),
+            spans,
+        });
+    }
+
+    pub fn set_message(&self, message: &str) {
+        let mut state = self.state.lock().unwrap();
+        if state.finished_at.is_some() {
+            return;
+        }
+        state.progress.message = message.to_string();
+        self.publish(OperationEvent::Progress {
+            progress: state.progress.clone(),
+        });
+    }
+
+    pub fn set_progress(&self, progress: u8, message: &str) {
+        let mut state = self.state.lock().unwrap();
+        if state.finished_at.is_some() {
+            return;
+        }
+        state.progress.progress = progress.min(100);
+        if !message.is_empty() {
+            state.progress.message = message.to_string();
+        }
+        self.publish(OperationEvent::Progress {
+            progress: state.progress.clone(),
+        });
+    }
+
+    pub fn cancel(&self) -> bool {
+        if self.is_finished() {
+            return false;
+        }
+        self.cancel_tx.send_replace(true);
+        true
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancel_tx.borrow()
+    }
+
+    pub async fn cancelled(&self) {
+        let mut receiver = self.cancel_tx.subscribe();
+        let _ = receiver.wait_for(|cancelled| *cancelled).await;
+    }
+
+    pub fn record_execution(&self, execution: ExecutionRecord) {
+        self.executions.lock().unwrap().push(execution);
+    }
+
+    pub fn take_execution(&self) -> Option<ExecutionRecord> {
+        let mut executions = self.executions.lock().unwrap();
+        let last = executions.pop();
+        executions.clear();
+        last
+    }
+
+    pub fn finish(&self, result: &InstallResult) {
+        let status = if self.is_cancelled() {
+            InstallStatus::Cancelled
+        } else if result.success {
+            InstallStatus::Completed
+        } else {
+            InstallStatus::Failed
+        };
+
+        let mut state = self.state.lock().unwrap();
+        if state.finished_at.is_some() {
+            return;
+        }
+        state.finished_at = Some(Utc::now().to_rfc3339());
+        state.progress.status = status;
+        state.progress.progress = 100;
+        state.progress.message = result.message.clone();
+        state.result = Some(result.clone());
+        self.publish(OperationEvent::Exit {
+            success: result.success,
+            message: result.message.clone(),
+        });
+    }
+
+    // Буфер и подписка берутся под одной блокировкой, чтобы подписчик
+    // не потерял и не получил дважды события между replay и live-потоком.
+    fn subscribe(&self) -> (Vec<OperationEvent>, broadcast::Receiver<OperationEvent>) {
+        let events = self.events.lock().unwrap();
+        (events.events(), self.tx.subscribe())
+    }
+
+    // Только новые события, без повторной отправки буфера.
+    pub fn subscribe_live(&self) -> broadcast::Receiver<OperationEvent> {
+        self.tx.subscribe()
+    }
+
+    pub fn stream(&self) -> impl Stream<Item = OperationEvent> {
+        let (buffered, receiver) = self.subscribe();
+        let finished = buffered.iter().any(OperationEvent::is_exit);
+
+        let live = stream::unfold((receiver, finished), |(mut receiver, finished)| async move {
+            if finished {
+                return None;
+            }
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        let finished = event.is_exit();
+                        return Some((event, (receiver, finished)));
+                    }
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+
+        stream::iter(buffered).chain(live)
+    }
+
+    fn publish(&self, event: OperationEvent) {
+        let mut events = self.events.lock().unwrap();
+        events.push(event.clone());
+        let _ = self.tx.send(event);
+    }
+}
+
+pub struct OperationManager {
+    operations: RwLock<HashMap<String, Arc<Operation>>>,
+    order: RwLock<Vec<String>>,
+    locks: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+    dependency_locks: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+}
+
+impl OperationManager {
+    pub fn new() -> Self {
+        Self {
+            operations: RwLock::new(HashMap::new()),
+            order: RwLock::new(Vec::new()),
+            locks: Mutex::new(HashMap::new()),
+            dependency_locks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn create(&self, installer_id: &str, action: InstallAction) -> Arc<Operation> {
+        let operation = Arc::new(Operation::new(installer_id, action));
+        let mut operations = self.operations.write().unwrap();
+        let mut order = self.order.write().unwrap();
+        operations.insert(operation.id().to_string(), Arc::clone(&operation));
+        order.push(operation.id().to_string());
+        Self::prune(&mut operations, &mut order);
+        operation
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<Operation>> {
+        let operations = self.operations.read().unwrap();
+        operations.get(id).map(Arc::clone)
+    }
+
+    pub fn list(&self) -> Vec<OperationInfo> {
+        let operations = self.operations.read().unwrap();
+        let order = self.order.read().unwrap();
+        order
+            .iter()
+            .rev()
+            .filter_map(|id| operations.get(id))
+            .map(|op| op.info())
+            .collect()
+    }
+
+    pub async fn lock_installer(&self, installer_id: &str) -> OwnedMutexGuard<()> {
+        Self::lock(&self.locks, installer_id).await
+    }
+
+    // Отдельная от `lock_installer` блокировка: её держат на время проверки и
+    // установки зависимости, а сама операция установки берёт `lock_installer`.
+    pub async fn lock_dependency(&self, installer_id: &str) -> OwnedMutexGuard<()> {
+        Self::lock(&self.dependency_locks, installer_id).await
+    }
+
+    async fn lock(locks: &Mutex<HashMap<String, Arc<AsyncMutex<()>>>>, installer_id: &str) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut locks = locks.lock().unwrap();
+            Arc::clone(locks.entry(installer_id.to_string()).or_default())
+        };
+        lock.lock_owned().await
+    }
+
+    pub fn latest_for(&self, installer_id: &str) -> Option<Arc<Operation>> {
+        let operations = self.operations.read().unwrap();
+        let order = self.order.read().unwrap();
+        order
+            .iter()
+            .rev()
+            .filter_map(|id| operations.get(id))
+            .find(|op| op.installer_id() == installer_id)
+            .map(Arc::clone)
+    }
+
+    fn prune(operations: &mut HashMap<String, Arc<Operation>>, order: &mut Vec<String>) {
+        let finished = order
+            .iter()
+            .filter(|id| operations.get(*id).map(|op| op.is_finished()).unwrap_or(true))
+            .count();
+        if finished <= MAX_FINISHED_OPERATIONS {
+            return;
+        }
+
+        let mut excess = finished - MAX_FINISHED_OPERATIONS;
+        order.retain(|id| {
+            if excess == 0 {
+                return true;
+            }
+            let done = operations.get(id).map(|op| op.is_finished()).unwrap_or(true);
+            if done {
+                operations.remove(id);
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+impl Default for OperationManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
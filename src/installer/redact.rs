@@ -0,0 +1,114 @@
+use regex::{Captures, Regex};
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+const MASK: &str = "***";
+// Слишком короткие значения переменных замаскировали бы половину вывода.
+const MIN_SECRET_LEN: usize = 4;
+const PATTERNS_FILE_ENV: &str = "KITSUNE_REDACT_PATTERNS_FILE";
+
+// Если в шаблоне есть группа `secret`, маскируется только она, иначе всё совпадение.
+const DEFAULT_PATTERNS: [&str; 5] = [
+    r#"(?i)\b(?:password|passwd|token|secret|api[_-]?key|access[_-]?key)\b\s*[:=]\s*["']?(?P<secret>[^\s"']+)"#,
+    r"(?i)\bbearer\s+(?P<secret>[A-Za-z0-9._~+/=-]{8,})",
+    r"://[^/\s:@]+:(?P<secret>[^/\s@]+)@",
+    r"\bgh[pousr]_[A-Za-z0-9]{36,}\b",
+    r"\bAKIA[0-9A-Z]{16}\b",
+];
+
+static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+
+fn patterns() -> &'static [Regex] {
+    PATTERNS.get_or_init(load_patterns)
+}
+
+// Встроенные шаблоны дополняются файлом из KITSUNE_REDACT_PATTERNS_FILE:
+// по одному регулярному выражению на строку, `#` — комментарий.
+fn load_patterns() -> Vec<Regex> {
+    let mut sources: Vec<String> = DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect();
+    if let Ok(path) = std::env::var(PATTERNS_FILE_ENV) {
+        match std::fs::read_to_string(&path) {
+            Ok(content) => sources.extend(
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(str::to_string),
+            ),
+            Err(e) => eprintln!("Не удалось прочитать шаблоны маскировки {}: {}", path, e),
+        }
+    }
+
+    sources
+        .iter()
+        .filter_map(|source| match Regex::new(source) {
+            Ok(regex) => Some(regex),
+            Err(e) => {
+                eprintln!("Некорректный шаблон маскировки {}: {}", source, e);
+                None
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    secrets: Vec<String>,
+}
+
+impl Redactor {
+    // Значения берутся из окружения сервера, которое наследуют скрипты.
+    pub fn with_secret_env(names: &[String]) -> Self {
+        let mut secrets: Vec<String> = names
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .filter(|value| value.len() >= MIN_SECRET_LEN)
+            .collect();
+        secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+        secrets.dedup();
+        Self { secrets }
+    }
+
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for secret in &self.secrets {
+            if text.contains(secret.as_str()) {
+                text = Cow::Owned(text.replace(secret.as_str(), MASK));
+            }
+        }
+        for pattern in patterns() {
+            if let Cow::Owned(replaced) = pattern.replace_all(&text, mask_captures) {
+                text = Cow::Owned(replaced);
+            }
+        }
+        text
+    }
+
+    pub fn redact_in_place(&self, text: &mut String) {
+        if let Cow::Owned(redacted) = self.redact(text) {
+            *text = redacted;
+        }
+    }
+
+    pub fn redact_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::String(text) => self.redact_in_place(text),
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|item| self.redact_json(item)),
+            serde_json::Value::Object(map) => map.values_mut().for_each(|item| self.redact_json(item)),
+            _ => {}
+        }
+    }
+}
+
+fn mask_captures(captures: &Captures) -> String {
+    let whole = captures.get(0).expect("совпадение всегда есть");
+    match captures.name("secret") {
+        Some(secret) => {
+            let text = whole.as_str();
+            let start = secret.start() - whole.start();
+            let end = secret.end() - whole.start();
+            format!("{}{}{}", &text[..start], MASK, &text[end..])
+        }
+        None => MASK.to_string(),
+    }
+}
@@ -0,0 +1,56 @@
+use crate::installer::script::ScriptInstallerConfig;
+use crate::installer::validation::Diagnostic;
+use jsonschema::{error::ValidationErrorKind, paths::PathChunk, JSONSchema};
+use serde_json::Value;
+use std::sync::OnceLock;
+
+const SCHEMA_TITLE: &str = "Kitsune Hub installer manifest";
+
+static SCHEMA: OnceLock<Value> = OnceLock::new();
+static VALIDATOR: OnceLock<JSONSchema> = OnceLock::new();
+
+// Схема строится из тех же типов, в которые десериализуется манифест,
+// поэтому редактор и загрузчик проверяют одно и то же.
+pub fn manifest_schema() -> &'static Value {
+    SCHEMA.get_or_init(|| {
+        let mut schema = schemars::schema_for!(ScriptInstallerConfig);
+        if let Some(metadata) = schema.schema.metadata.as_mut() {
+            metadata.title = Some(SCHEMA_TITLE.to_string());
+        }
+        serde_json::to_value(schema).expect("схема манифеста сериализуется в JSON")
+    })
+}
+
+fn validator() -> &'static JSONSchema {
+    VALIDATOR.get_or_init(|| JSONSchema::compile(manifest_schema()).expect("схема манифеста корректна"))
+}
+
+// Каждое нарушение схемы — отдельная ошибка с путём к полю через точку
+// (`scripts.install`, `dependencies.0`).
+pub fn validate_manifest(manifest: &Value) -> Vec<Diagnostic> {
+    let Err(errors) = validator().validate(manifest) else {
+        return Vec::new();
+    };
+
+    errors
+        .map(|error| {
+            let mut path: Vec<String> = error
+                .instance_path
+                .iter()
+                .map(|chunk| match chunk {
+                    PathChunk::Property(name) => name.to_string(),
+                    PathChunk::Index(index) => index.to_string(),
+                    PathChunk::Keyword(keyword) => keyword.to_string(),
+                })
+                .collect();
+            if let ValidationErrorKind::Required { property: Value::String(property) } = &error.kind {
+                path.push(property.clone());
+            }
+            let diagnostic = Diagnostic::error(format!("Манифест не соответствует схеме: {}", error));
+            match path.is_empty() {
+                true => diagnostic,
+                false => diagnostic.with_field(path.join(".")),
+            }
+        })
+        .collect()
+}
@@ -1,64 +1,292 @@
-use crate::installer::{Installer, InstallResult};
-use crate::installers::base::run_command;
+use crate::installer::{Installer, InstallerDetails, InstallerError, InstallationState, InstallResult};
+use crate::installer::operation::Operation;
+use crate::installer::redact::Redactor;
+use crate::installer::version::VersionScheme;
+use crate::installers::base::{run_command, RunOptions};
 use async_trait::async_trait;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use tokio::time::Duration;
+use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+const DEFAULT_QUERY_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_ACTION_TIMEOUT_SECS: u64 = 3600;
+const RESULT_FILE_ENV: &str = "KITSUNE_RESULT_FILE";
+
+// Ограничения `schemars` попадают в публикуемую схему манифеста и
+// проверяются загрузчиком до десериализации.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ScriptInstallerConfig {
+    // ID попадает в URL и имена файлов логов.
+    #[schemars(regex(pattern = r"^[a-z0-9][a-z0-9._-]*$"))]
     pub id: String,
+    #[schemars(regex(pattern = r"\S"))]
     pub name: String,
     pub description: String,
+    // Если не задана, выводится загрузчиком из пути к манифесту.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    #[schemars(regex(pattern = r"\S"))]
     pub category: String,
-    pub dependencies: Vec<String>,
+    #[serde(default)]
+    pub dependencies: Vec<DependencySpec>,
+    #[serde(default)]
+    pub version_scheme: VersionScheme,
+    #[serde(default)]
+    pub result_format: ResultFormat,
+    #[serde(default)]
+    pub secret_env: Vec<String>,
     pub scripts: ScriptPaths,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ScriptReport {
+    #[serde(default)]
+    pub installed: Option<bool>,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub install_path: Option<String>,
+    #[serde(default)]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    pub messages: Vec<String>,
+}
+
+impl ScriptReport {
+    fn redact(&mut self, redactor: &Redactor) {
+        if let Some(version) = &mut self.version {
+            redactor.redact_in_place(version);
+        }
+        if let Some(install_path) = &mut self.install_path {
+            redactor.redact_in_place(install_path);
+        }
+        self.metadata.values_mut().for_each(|value| redactor.redact_json(value));
+        self.messages.iter_mut().for_each(|message| redactor.redact_in_place(message));
+    }
+}
+
+struct ScriptOutput {
+    stdout: String,
+    report: Option<ScriptReport>,
+}
+
+impl ScriptOutput {
+    fn text(&self) -> Option<String> {
+        let text = self.stdout.trim();
+        if text.is_empty() {
+            None
+        } else {
+            Some(text.to_string())
+        }
+    }
+
+    fn summary(&self) -> String {
+        let mut summary = self.stdout.trim().to_string();
+        if let Some(report) = &self.report {
+            for message in &report.messages {
+                if !summary.is_empty() {
+                    summary.push('\n');
+                }
+                summary.push_str(message);
+            }
+        }
+        summary
+    }
+}
+
+fn parse_installed(output: &str) -> bool {
+    let output = output.trim().to_lowercase();
+    output == "1" || output == "true" || output == "installed"
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum DependencySpec {
+    Id(String),
+    Constrained { id: String, version: Option<String> },
+}
+
+impl DependencySpec {
+    pub fn id(&self) -> &str {
+        match self {
+            DependencySpec::Id(id) => id,
+            DependencySpec::Constrained { id, .. } => id,
+        }
+    }
+
+    pub fn version(&self) -> Option<&str> {
+        match self {
+            DependencySpec::Id(_) => None,
+            DependencySpec::Constrained { version, .. } => version.as_deref(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ScriptPaths {
+    #[schemars(required, regex(pattern = r"\S"))]
     pub install: Option<String>,
     pub update: Option<String>,
     pub uninstall: Option<String>,
     pub check: Option<String>,
     pub version: Option<String>,
     pub latest_version: Option<String>,
+    #[serde(default)]
+    pub cleanup: Option<String>,
+    #[serde(default)]
+    pub timeouts: ScriptTimeouts,
+}
+
+impl ScriptPaths {
+    pub fn entries(&self) -> [(&'static str, Option<&str>); 7] {
+        [
+            ("install", self.install.as_deref()),
+            ("update", self.update.as_deref()),
+            ("uninstall", self.uninstall.as_deref()),
+            ("check", self.check.as_deref()),
+            ("version", self.version.as_deref()),
+            ("latest_version", self.latest_version.as_deref()),
+            ("cleanup", self.cleanup.as_deref()),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ScriptTimeouts {
+    pub default: Option<u64>,
+    pub install: Option<u64>,
+    pub update: Option<u64>,
+    pub uninstall: Option<u64>,
+    pub check: Option<u64>,
+    pub version: Option<u64>,
+    pub latest_version: Option<u64>,
+    pub cleanup: Option<u64>,
+}
+
+impl ScriptTimeouts {
+    fn resolve(&self, specific: Option<u64>, fallback: u64) -> Duration {
+        Duration::from_secs(specific.or(self.default).unwrap_or(fallback))
+    }
 }
 
 pub struct ScriptInstaller {
     config: ScriptInstallerConfig,
     base_path: PathBuf,
+    revision: String,
 }
 
 impl ScriptInstaller {
-    pub fn new(config: ScriptInstallerConfig, base_path: PathBuf) -> Self {
-        Self { config, base_path }
+    pub fn new(config: ScriptInstallerConfig, base_path: PathBuf, revision: String) -> Self {
+        Self {
+            config,
+            base_path,
+            revision,
+        }
     }
 
-    fn script_path(&self, script_name: &str) -> Option<PathBuf> {
-        if script_name.is_empty() {
-            return None;
-        }
+    pub fn config(&self) -> &ScriptInstallerConfig {
+        &self.config
+    }
+
+    pub fn revision(&self) -> &str {
+        &self.revision
+    }
+
+    // Пути всех скриптов, на которые ссылается манифест, существующих или нет.
+    pub fn script_files(&self) -> Vec<(&'static str, PathBuf)> {
+        self.config
+            .scripts
+            .entries()
+            .into_iter()
+            .filter_map(|(action, name)| Some((action, name.filter(|name| !name.is_empty())?)))
+            .map(|(action, name)| (action, self.base_path.join(name)))
+            .collect()
+    }
+
+    fn script_path(&self, action: &str, script_name: Option<&str>) -> Result<PathBuf, InstallerError> {
+        let script_name = script_name
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| InstallerError::ScriptNotConfigured {
+                action: action.to_string(),
+            })?;
         let path = self.base_path.join(script_name);
         if path.exists() {
-            Some(path)
+            Ok(path)
         } else {
-            None
+            Err(InstallerError::ScriptNotFound { script: path })
         }
     }
 
-    async fn run_script(&self, script_path: Option<PathBuf>) -> Result<String, String> {
-        let path = script_path.ok_or_else(|| "Скрипт не найден".to_string())?;
-        
-        if !path.exists() {
-            return Err(format!("Скрипт не существует: {}", path.display()));
+    async fn run_script(
+        &self,
+        action: &str,
+        script_name: Option<&str>,
+        timeout: Duration,
+        operation: Option<&Operation>,
+    ) -> Result<ScriptOutput, InstallerError> {
+        let path = self.script_path(action, script_name)?;
+
+        let result_file = (self.config.result_format == ResultFormat::Json).then(|| {
+            std::env::temp_dir().join(format!("kitsune-{}-{}.json", self.config.id, Uuid::new_v4()))
+        });
+        let mut env = Vec::new();
+        if let Some(result_file) = &result_file {
+            env.push((RESULT_FILE_ENV.to_string(), result_file.to_string_lossy().to_string()));
         }
 
         let script_str = path.to_string_lossy().to_string();
-        tokio::task::spawn_blocking(move || {
-            run_command("bash", &[&script_str])
+        let redactor = Redactor::with_secret_env(&self.config.secret_env);
+        let options = RunOptions {
+            operation,
+            timeout: Some(timeout),
+            env,
+            script: Some(path.clone()),
+            manifest_revision: Some(self.revision.clone()),
+            redactor: redactor.clone(),
+        };
+        let stdout = run_command("bash", &[&script_str], options).await;
+
+        let report = result_file.and_then(|result_file| {
+            let content = std::fs::read_to_string(&result_file).ok();
+            let _ = std::fs::remove_file(&result_file);
+            let mut report = serde_json::from_str::<ScriptReport>(&content?).ok()?;
+            report.redact(&redactor);
+            Some(report)
+        });
+
+        Ok(ScriptOutput {
+            stdout: stdout.map_err(|e| e.with_script(path))?,
+            report,
         })
-        .await
-        .map_err(|e| format!("Ошибка выполнения задачи: {}", e))?
+    }
+
+    async fn run_check(&self) -> Option<ScriptOutput> {
+        let check_script = self.config.scripts.check.as_deref()?;
+        let timeouts = &self.config.scripts.timeouts;
+        let timeout = timeouts.resolve(timeouts.check, DEFAULT_QUERY_TIMEOUT_SECS);
+        self.run_script("check", Some(check_script), timeout, None).await.ok()
+    }
+
+    async fn run_version(&self) -> Option<String> {
+        let version_script = self.config.scripts.version.as_deref()?;
+        let timeouts = &self.config.scripts.timeouts;
+        let timeout = timeouts.resolve(timeouts.version, DEFAULT_QUERY_TIMEOUT_SECS);
+        let output = self.run_script("version", Some(version_script), timeout, None).await.ok()?;
+        output
+            .report
+            .as_ref()
+            .and_then(|r| r.version.clone())
+            .or_else(|| output.text())
     }
 }
 
@@ -81,71 +309,124 @@ impl Installer for ScriptInstaller {
     }
 
     fn dependencies(&self) -> Vec<String> {
-        self.config.dependencies.clone()
+        self.config.dependencies.iter().map(|d| d.id().to_string()).collect()
+    }
+
+    fn dependency_versions(&self) -> BTreeMap<String, String> {
+        self.config
+            .dependencies
+            .iter()
+            .filter_map(|d| Some((d.id().to_string(), d.version()?.to_string())))
+            .collect()
+    }
+
+    fn version_scheme(&self) -> VersionScheme {
+        self.config.version_scheme
     }
 
     async fn check_installed(&self) -> bool {
-        if let Some(check_script) = &self.config.scripts.check {
-            let script_path = self.script_path(check_script);
-            if let Ok(output) = self.run_script(script_path).await {
-                output.trim() == "1" || output.trim().to_lowercase() == "true" || output.trim().to_lowercase() == "installed"
-            } else {
-                false
-            }
-        } else {
-            false
+        match self.run_check().await {
+            Some(output) => match output.report.and_then(|r| r.installed) {
+                Some(installed) => installed,
+                None => parse_installed(&output.stdout),
+            },
+            None => false,
         }
     }
 
     async fn get_current_version(&self) -> Option<String> {
-        if let Some(version_script) = &self.config.scripts.version {
-            let script_path = self.script_path(version_script);
-            self.run_script(script_path).await.ok().map(|v| v.trim().to_string())
-        } else {
-            None
-        }
+        self.run_version().await
     }
 
     async fn get_latest_version(&self) -> Option<String> {
-        if let Some(latest_script) = &self.config.scripts.latest_version {
-            let script_path = self.script_path(latest_script);
-            self.run_script(script_path).await.ok().map(|v| v.trim().to_string())
-        } else {
-            None
-        }
+        let latest_script = self.config.scripts.latest_version.as_deref()?;
+        let timeouts = &self.config.scripts.timeouts;
+        let timeout = timeouts.resolve(timeouts.latest_version, DEFAULT_QUERY_TIMEOUT_SECS);
+        let output = self
+            .run_script("latest_version", Some(latest_script), timeout, None)
+            .await
+            .ok()?;
+        output
+            .report
+            .as_ref()
+            .and_then(|r| r.version.clone())
+            .or_else(|| output.text())
     }
 
-    async fn install(&self) -> Result<InstallResult, String> {
-        let script_path = self.script_path(self.config.scripts.install.as_deref().unwrap_or(""));
-        match self.run_script(script_path).await {
-            Ok(output) => Ok(InstallResult {
-                success: true,
-                message: format!("Установка завершена: {}", output.trim()),
-            }),
-            Err(e) => Err(format!("Ошибка установки: {}", e)),
+    async fn inspect(&self) -> InstallationState {
+        let Some(output) = self.run_check().await else {
+            return InstallationState::default();
+        };
+        let report = output.report.unwrap_or_default();
+        let installed = report.installed.unwrap_or_else(|| parse_installed(&output.stdout));
+        if !installed {
+            return InstallationState::default();
         }
-    }
 
-    async fn update(&self) -> Result<InstallResult, String> {
-        let script_path = self.script_path(self.config.scripts.update.as_deref().unwrap_or(""));
-        match self.run_script(script_path).await {
-            Ok(output) => Ok(InstallResult {
-                success: true,
-                message: format!("Обновление завершено: {}", output.trim()),
-            }),
-            Err(e) => Err(format!("Ошибка обновления: {}", e)),
+        let current_version = match report.version {
+            Some(version) => Some(version),
+            None => self.run_version().await,
+        };
+
+        InstallationState {
+            installed,
+            current_version,
+            details: InstallerDetails {
+                install_path: report.install_path,
+                metadata: report.metadata,
+                messages: report.messages,
+            },
         }
     }
 
-    async fn uninstall(&self) -> Result<InstallResult, String> {
-        let script_path = self.script_path(self.config.scripts.uninstall.as_deref().unwrap_or(""));
-        match self.run_script(script_path).await {
-            Ok(output) => Ok(InstallResult {
-                success: true,
-                message: format!("Удаление завершено: {}", output.trim()),
-            }),
-            Err(e) => Err(format!("Ошибка удаления: {}", e)),
-        }
+    async fn install(&self, operation: &Operation) -> Result<InstallResult, InstallerError> {
+        let timeouts = &self.config.scripts.timeouts;
+        let timeout = timeouts.resolve(timeouts.install, DEFAULT_ACTION_TIMEOUT_SECS);
+        let output = self
+            .run_script("install", self.config.scripts.install.as_deref(), timeout, Some(operation))
+            .await?;
+        Ok(InstallResult {
+            success: true,
+            message: format!("Установка завершена: {}", output.summary()),
+            error: None,
+        })
+    }
+
+    async fn update(&self, operation: &Operation) -> Result<InstallResult, InstallerError> {
+        let timeouts = &self.config.scripts.timeouts;
+        let timeout = timeouts.resolve(timeouts.update, DEFAULT_ACTION_TIMEOUT_SECS);
+        let output = self
+            .run_script("update", self.config.scripts.update.as_deref(), timeout, Some(operation))
+            .await?;
+        Ok(InstallResult {
+            success: true,
+            message: format!("Обновление завершено: {}", output.summary()),
+            error: None,
+        })
     }
-}
 
+    async fn uninstall(&self, operation: &Operation) -> Result<InstallResult, InstallerError> {
+        let timeouts = &self.config.scripts.timeouts;
+        let timeout = timeouts.resolve(timeouts.uninstall, DEFAULT_ACTION_TIMEOUT_SECS);
+        let output = self
+            .run_script("uninstall", self.config.scripts.uninstall.as_deref(), timeout, Some(operation))
+            .await?;
+        Ok(InstallResult {
+            success: true,
+            message: format!("Удаление завершено: {}", output.summary()),
+            error: None,
+        })
+    }
+
+    async fn cleanup(&self) -> Option<Result<InstallResult, InstallerError>> {
+        let cleanup_script = self.config.scripts.cleanup.as_deref()?;
+        let timeouts = &self.config.scripts.timeouts;
+        let timeout = timeouts.resolve(timeouts.cleanup, DEFAULT_ACTION_TIMEOUT_SECS);
+        let output = self.run_script("cleanup", Some(cleanup_script), timeout, None).await;
+        Some(output.map(|output| InstallResult {
+            success: true,
+            message: format!("Очистка завершена: {}", output.summary()),
+            error: None,
+        }))
+    }
+}
@@ -0,0 +1,119 @@
+use crate::installer::error::InstallerError;
+use crate::installer::script::ScriptInstallerConfig;
+use crate::installer::version::{satisfies, VersionScheme};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            field: None,
+            line: None,
+            column: None,
+        }
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(message)
+        }
+    }
+
+    pub fn with_field(mut self, field: impl Into<String>) -> Self {
+        self.field = Some(field.into());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl From<&InstallerError> for Diagnostic {
+    fn from(error: &InstallerError) -> Self {
+        let mut diagnostic = Diagnostic::error(error.to_string());
+        if let InstallerError::InvalidConfig { line, column, .. } = error {
+            diagnostic.line = *line;
+            diagnostic.column = *column;
+        }
+        diagnostic
+    }
+}
+
+// Проверки, которые не выражаются схемой манифеста (см. `schema.rs`):
+// ошибки не дают загрузить установщик, предупреждения — нет.
+pub fn validate_config(config: &ScriptInstallerConfig) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    if config.description.trim().is_empty() {
+        diagnostics.push(Diagnostic::warning("Описание не заполнено").with_field("description"));
+    }
+
+    let mut seen = HashSet::new();
+    for dependency in &config.dependencies {
+        let id = dependency.id();
+        if id == config.id {
+            diagnostics.push(Diagnostic::error("Установщик не может зависеть от самого себя").with_field("dependencies"));
+        } else if !seen.insert(id) {
+            diagnostics.push(
+                Diagnostic::warning(format!("Зависимость {} указана несколько раз", id)).with_field("dependencies"),
+            );
+        }
+    }
+
+    diagnostics
+}
+
+// Требование к версии разбирается по схеме самой зависимости, поэтому
+// проверяется при сборке каталога, когда зависимость уже известна.
+pub fn check_requirement(dependency: &str, requirement: &str, scheme: VersionScheme) -> Option<Diagnostic> {
+    let sample = match scheme {
+        VersionScheme::Semver => "0.0.0",
+        VersionScheme::Date => "2000.01.01",
+        VersionScheme::Exact => "",
+    };
+    let message = satisfies(sample, requirement, scheme).err()?;
+    Some(Diagnostic::warning(format!("{}: {}", dependency, message)).with_field("dependencies"))
+}
+
+// Наличие скриптов проверяется при каждой пересборке каталога: файлы могут
+// появиться или пропасть без изменения манифеста.
+pub fn check_scripts(scripts: &[(&'static str, &Path)]) -> Vec<Diagnostic> {
+    scripts
+        .iter()
+        .filter_map(|(action, path)| {
+            let field = format!("scripts.{}", action);
+            if !path.is_file() {
+                Some(Diagnostic::warning(format!("Скрипт не существует: {}", path.display())).with_field(field))
+            } else if let Err(e) = File::open(path) {
+                Some(Diagnostic::warning(format!("Скрипт недоступен для чтения: {}: {}", path.display(), e)).with_field(field))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,241 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionScheme {
+    #[default]
+    Semver,
+    Date,
+    Exact,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Identifier {
+    Numeric(u64),
+    Alpha(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ParsedVersion {
+    numbers: Vec<u64>,
+    pre_release: Vec<Identifier>,
+}
+
+impl ParsedVersion {
+    fn cmp_numbers(&self, other: &Self) -> Ordering {
+        let len = self.numbers.len().max(other.numbers.len());
+        for i in 0..len {
+            let a = self.numbers.get(i).copied().unwrap_or(0);
+            let b = other.numbers.get(i).copied().unwrap_or(0);
+            match a.cmp(&b) {
+                Ordering::Equal => continue,
+                ordering => return ordering,
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+impl Ord for ParsedVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_numbers(other).then_with(|| {
+            match (self.pre_release.is_empty(), other.pre_release.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre_release.cmp(&other.pre_release),
+            }
+        })
+    }
+}
+
+impl PartialOrd for ParsedVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn strip_prefix(version: &str) -> &str {
+    let version = version.trim();
+    version
+        .strip_prefix('v')
+        .or_else(|| version.strip_prefix('V'))
+        .unwrap_or(version)
+}
+
+fn parse_identifier(part: &str) -> Identifier {
+    match part.parse::<u64>() {
+        Ok(n) => Identifier::Numeric(n),
+        Err(_) => Identifier::Alpha(part.to_string()),
+    }
+}
+
+fn parse_semver(version: &str) -> Option<ParsedVersion> {
+    let version = strip_prefix(version);
+    let version = version.split('+').next().unwrap_or(version);
+    let (core, pre_release) = match version.split_once('-') {
+        Some((core, pre_release)) => (core, pre_release),
+        None => (version, ""),
+    };
+
+    let numbers = core
+        .split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    let pre_release = if pre_release.is_empty() {
+        Vec::new()
+    } else {
+        pre_release.split('.').map(parse_identifier).collect()
+    };
+
+    Some(ParsedVersion { numbers, pre_release })
+}
+
+fn parse_date(version: &str) -> Option<ParsedVersion> {
+    let numbers = strip_prefix(version)
+        .split(['.', '-', '_', '/'])
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(ParsedVersion {
+        numbers,
+        pre_release: Vec::new(),
+    })
+}
+
+// None означает, что версии несравнимы (не разобрались по выбранной схеме).
+pub fn compare_versions(current: &str, latest: &str, scheme: VersionScheme) -> Option<Ordering> {
+    match scheme {
+        VersionScheme::Semver => Some(parse_semver(current)?.cmp(&parse_semver(latest)?)),
+        VersionScheme::Date => Some(parse_date(current)?.cmp(&parse_date(latest)?)),
+        VersionScheme::Exact => {
+            if current.trim() == latest.trim() {
+                Some(Ordering::Equal)
+            } else {
+                Some(Ordering::Less)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RequirementOp {
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+    Caret,
+    Tilde,
+}
+
+fn parse_comparator(part: &str) -> (RequirementOp, &str) {
+    const OPS: [(&str, RequirementOp); 8] = [
+        (">=", RequirementOp::GreaterEq),
+        ("<=", RequirementOp::LessEq),
+        ("==", RequirementOp::Exact),
+        (">", RequirementOp::Greater),
+        ("<", RequirementOp::Less),
+        ("=", RequirementOp::Exact),
+        ("^", RequirementOp::Caret),
+        ("~", RequirementOp::Tilde),
+    ];
+
+    for (prefix, op) in OPS {
+        if let Some(rest) = part.strip_prefix(prefix) {
+            return (op, rest.trim());
+        }
+    }
+    (RequirementOp::Exact, part)
+}
+
+fn parse_with_scheme(version: &str, scheme: VersionScheme) -> Option<ParsedVersion> {
+    match scheme {
+        VersionScheme::Semver => parse_semver(version),
+        VersionScheme::Date => parse_date(version),
+        VersionScheme::Exact => None,
+    }
+}
+
+fn upper_bound(bound: &ParsedVersion, op: RequirementOp) -> ParsedVersion {
+    let significant = bound.numbers.len();
+    let index = match op {
+        RequirementOp::Caret => bound
+            .numbers
+            .iter()
+            .position(|n| *n != 0)
+            .unwrap_or(significant.saturating_sub(1)),
+        _ => significant.min(2).saturating_sub(1),
+    };
+
+    let mut numbers = bound.numbers[..=index.min(significant.saturating_sub(1))].to_vec();
+    if let Some(last) = numbers.last_mut() {
+        *last += 1;
+    }
+    // Нижняя граница pre-release, чтобы `<2.0.0` отсекал и `2.0.0-beta`.
+    ParsedVersion {
+        numbers,
+        pre_release: vec![Identifier::Numeric(0)],
+    }
+}
+
+fn comparator_matches(version: &ParsedVersion, op: RequirementOp, bound: &ParsedVersion) -> bool {
+    match op {
+        RequirementOp::Exact => {
+            bound
+                .numbers
+                .iter()
+                .enumerate()
+                .all(|(i, n)| version.numbers.get(i).copied().unwrap_or(0) == *n)
+                && (bound.pre_release.is_empty() || version.pre_release == bound.pre_release)
+        }
+        RequirementOp::Greater => version > bound,
+        RequirementOp::GreaterEq => version >= bound,
+        RequirementOp::Less => version < bound,
+        RequirementOp::LessEq => version <= bound,
+        RequirementOp::Caret | RequirementOp::Tilde => version >= bound && *version < upper_bound(bound, op),
+    }
+}
+
+// Требование — набор условий через пробел или запятую, все должны выполняться:
+// `>=18 <21`, `^1.2`, `~2.4.0`, `1.2` (совпадение по префиксу).
+pub fn satisfies(version: &str, requirement: &str, scheme: VersionScheme) -> Result<bool, String> {
+    if scheme == VersionScheme::Exact {
+        let expected = requirement.trim().trim_start_matches('=').trim();
+        if expected.contains(['<', '>', '=', '^', '~']) {
+            return Err(format!(
+                "Схема exact допускает только точную версию, а не \"{}\"",
+                requirement
+            ));
+        }
+        return Ok(version.trim() == expected);
+    }
+
+    let parsed = parse_with_scheme(version, scheme)
+        .ok_or_else(|| format!("Не удалось разобрать версию \"{}\"", version))?;
+
+    let mut parts: Vec<String> = Vec::new();
+    let mut pending_op = String::new();
+    for token in requirement.split([' ', ',']).filter(|t| !t.is_empty()) {
+        if token.chars().all(|c| "<>=^~".contains(c)) {
+            pending_op.push_str(token);
+        } else {
+            parts.push(format!("{}{}", std::mem::take(&mut pending_op), token));
+        }
+    }
+    if !pending_op.is_empty() {
+        return Err(format!("Некорректное требование к версии \"{}\"", requirement));
+    }
+
+    let mut matched = true;
+    for part in &parts {
+        let (op, bound) = parse_comparator(part);
+        let bound = parse_with_scheme(bound, scheme)
+            .ok_or_else(|| format!("Некорректное требование к версии \"{}\"", requirement))?;
+        matched &= comparator_matches(&parsed, op, &bound);
+    }
+    Ok(matched)
+}
@@ -1,21 +1,274 @@
+use crate::installer::ansi::{self, StyledSpan};
+use crate::installer::error::InstallerError;
+use crate::installer::operation::{Operation, OutputStream};
+use crate::installer::redact::Redactor;
+use crate::installer::ExecutionRecord;
+use chrono::Utc;
+use std::borrow::Cow;
+use std::os::unix::process::ExitStatusExt;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::Command;
+use tokio::time::{sleep, Duration};
 
-pub fn run_command(command: &str, args: &[&str]) -> Result<String, String> {
-    let rt = tokio::runtime::Runtime::new().map_err(|e| format!("Ошибка создания runtime: {}", e))?;
-    
-    rt.block_on(async {
-        let output = Command::new(command)
-            .args(args)
-            .output()
-            .await
-            .map_err(|e| format!("Ошибка выполнения команды: {}", e))?;
-
-        if output.status.success() {
-            Ok(String::from_utf8_lossy(&output.stdout).to_string())
-        } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            Err(format!("Команда завершилась с ошибкой: {}", stderr))
+const TERMINATE_GRACE_PERIOD: Duration = Duration::from_secs(10);
+const TERMINATE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const PROGRESS_PREFIX: &str = "::progress";
+const STDERR_TAIL_LINES: usize = 20;
+
+#[derive(Default)]
+pub struct RunOptions<'a> {
+    pub operation: Option<&'a Operation>,
+    pub timeout: Option<Duration>,
+    pub env: Vec<(String, String)>,
+    pub script: Option<PathBuf>,
+    pub manifest_revision: Option<String>,
+    pub redactor: Redactor,
+}
+
+pub async fn run_command(command: &str, args: &[&str], options: RunOptions<'_>) -> Result<String, InstallerError> {
+    let RunOptions {
+        operation,
+        timeout,
+        env,
+        script,
+        manifest_revision,
+        redactor,
+    } = options;
+    let spawn_failed = |message: String| InstallerError::SpawnFailed {
+        command: command.to_string(),
+        script: None,
+        message,
+    };
+
+    // Отмена, пришедшая до запуска, не должна запускать скрипт.
+    if operation.is_some_and(|op| op.is_cancelled()) {
+        return Err(InstallerError::Cancelled);
+    }
+
+    let started_at = Utc::now();
+    let started = Instant::now();
+    let mut child = Command::new(command)
+        .args(args)
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| spawn_failed(e.to_string()))?;
+
+    let pid = child.id();
+    let stdout = child.stdout.take().ok_or_else(|| spawn_failed("Не удалось получить stdout".to_string()))?;
+    let stderr = child.stderr.take().ok_or_else(|| spawn_failed("Не удалось получить stderr".to_string()))?;
+
+    let run = async {
+        tokio::join!(
+            read_lines(stdout, OutputStream::Stdout, operation, &redactor),
+            read_lines(stderr, OutputStream::Stderr, operation, &redactor),
+            child.wait(),
+        )
+    };
+
+    let timed_out = AtomicBool::new(false);
+    let watchdog = async {
+        let cancelled = async {
+            match operation {
+                Some(operation) => operation.cancelled().await,
+                None => std::future::pending().await,
+            }
+        };
+        let deadline = async {
+            match timeout {
+                Some(timeout) => sleep(timeout).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            biased;
+            _ = cancelled => {}
+            _ = deadline => timed_out.store(true, Ordering::SeqCst),
+        }
+        if let Some(pid) = pid {
+            terminate_process_group(pid);
+        }
+        std::future::pending::<()>().await
+    };
+
+    // watchdog опрашивается первым, чтобы уже поступившая отмена или истёкший
+    // лимит успели завершить группу процессов до того, как `run` вернёт результат.
+    let (stdout, stderr, status) = tokio::select! {
+        biased;
+        _ = watchdog => unreachable!(),
+        result = run => result,
+    };
+
+    let status = status.map_err(|e| spawn_failed(e.to_string()))?;
+
+    if let Some(operation) = operation {
+        operation.record_execution(ExecutionRecord {
+            script: script.map(|path| path.to_string_lossy().to_string()),
+            manifest_revision,
+            exit_code: status.code(),
+            signal: status.signal(),
+            started_at: started_at.to_rfc3339(),
+            finished_at: Utc::now().to_rfc3339(),
+            duration_ms: started.elapsed().as_millis() as u64,
+            stdout: stdout.text.clone(),
+            stderr: stderr.text.clone(),
+            stdout_raw: stdout.raw_if_changed(),
+            stderr_raw: stderr.raw_if_changed(),
+            stdout_spans: stdout.styled_spans(),
+            stderr_spans: stderr.styled_spans(),
+        });
+    }
+    let (stdout, stderr) = (stdout.text, stderr.text);
+
+    if timed_out.load(Ordering::SeqCst) {
+        Err(InstallerError::Timeout {
+            script: None,
+            timeout_secs: timeout.map(|t| t.as_secs()).unwrap_or_default(),
+        })
+    } else if operation.map(|op| op.is_cancelled()).unwrap_or(false) {
+        Err(InstallerError::Cancelled)
+    } else if status.success() {
+        Ok(stdout)
+    } else {
+        Err(InstallerError::NonZeroExit {
+            script: None,
+            exit_code: status.code(),
+            stderr_tail: tail_lines(&stderr, STDERR_TAIL_LINES),
+        })
+    }
+}
+
+fn tail_lines(text: &str, count: usize) -> String {
+    let lines: Vec<&str> = text.trim_end().lines().collect();
+    lines[lines.len().saturating_sub(count)..].join("\n")
+}
+
+// SIGKILL отправляется из отдельной задачи: лидер группы может завершиться
+// по SIGTERM раньше потомков, и тогда `run_command` перестаёт опрашивать
+// watchdog, а игнорирующие SIGTERM процессы группы остались бы жить.
+// Пока в группе есть процессы, её ID не достанется другой группе; опустевшую
+// группу задача больше не трогает, чтобы не задеть чужие процессы.
+fn terminate_process_group(pid: u32) {
+    signal_process_group(pid, libc::SIGTERM);
+    tokio::spawn(async move {
+        let deadline = Instant::now() + TERMINATE_GRACE_PERIOD;
+        while Instant::now() < deadline {
+            if !process_group_alive(pid) {
+                return;
+            }
+            sleep(TERMINATE_POLL_INTERVAL).await;
+        }
+        if process_group_alive(pid) {
+            signal_process_group(pid, libc::SIGKILL);
         }
-    })
+    });
 }
 
+fn process_group_alive(pid: u32) -> bool {
+    unsafe { libc::kill(-(pid as libc::pid_t), 0) == 0 }
+}
+
+fn signal_process_group(pid: u32, signal: libc::c_int) {
+    // Дочерний процесс запускается лидером собственной группы (process_group(0)),
+    // поэтому отрицательный pid адресует сигнал всему дереву скрипта.
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), signal);
+    }
+}
+
+// Вывод одного потока: `text` — то, что показал бы терминал, без
+// управляющих последовательностей; `raw` — исходные байты.
+#[derive(Default)]
+struct CapturedOutput {
+    text: String,
+    raw: String,
+    spans: Vec<StyledSpan>,
+    styled: bool,
+}
+
+impl CapturedOutput {
+    fn raw_if_changed(&self) -> Option<String> {
+        (self.raw != self.text).then(|| self.raw.clone())
+    }
+
+    fn styled_spans(&self) -> Option<Vec<StyledSpan>> {
+        self.styled.then(|| self.spans.clone())
+    }
+}
+
+// Секреты маскируются построчно до того, как строка попадёт в поток
+// операции, результат или журнал.
+async fn read_lines<R: AsyncRead + Unpin>(
+    reader: R,
+    stream: OutputStream,
+    operation: Option<&Operation>,
+    redactor: &Redactor,
+) -> CapturedOutput {
+    let mut reader = BufReader::new(reader);
+    let mut captured = CapturedOutput::default();
+    let mut buf = Vec::new();
+
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                let raw = String::from_utf8_lossy(&buf);
+                let raw = redactor.redact(&raw);
+                let newline = raw.ends_with('\n');
+                let mut line = ansi::render_line(raw.trim_end_matches('\n'));
+                // Секрет, разбитый управляющими последовательностями, виден
+                // только после их удаления; такую строку оставляем без оформления,
+                // а вместо исходных байтов сохраняем замаскированный текст.
+                let mut masked = false;
+                if let Cow::Owned(text) = redactor.redact(&line.text) {
+                    line = ansi::RenderedLine::plain(text);
+                    masked = true;
+                }
+                if let Some((progress, message)) = parse_progress_line(&line.text) {
+                    if let Some(operation) = operation {
+                        operation.set_progress(progress, message);
+                    }
+                    continue;
+                }
+                let styled = line.is_styled();
+                if let Some(operation) = operation {
+                    operation.output(stream, &line.text, styled.then(|| line.spans.clone()));
+                }
+                captured.styled |= styled;
+                match masked {
+                    true => captured.raw.push_str(&line.text),
+                    false => captured.raw.push_str(raw.trim_end_matches('\n')),
+                }
+                captured.text.push_str(&line.text);
+                if newline {
+                    captured.raw.push('\n');
+                    captured.text.push('\n');
+                }
+                ansi::append_line(&mut captured.spans, line, newline);
+            }
+        }
+    }
+
+    captured
+}
+
+// Строка протокола прогресса: `::progress <0-100> [сообщение]`.
+fn parse_progress_line(line: &str) -> Option<(u8, &str)> {
+    let rest = line.strip_prefix(PROGRESS_PREFIX)?;
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    let rest = rest.trim_start();
+    let (value, message) = rest.split_once(' ').unwrap_or((rest, ""));
+    let value = value.trim_end_matches('%').parse::<u8>().ok()?;
+    Some((value.min(100), message.trim()))
+}